@@ -0,0 +1,73 @@
+//! Loads a synthetic task store with and without the index.
+//!
+//! ```text
+//! cargo run --release --example index_bench [number of files, 50000 by default]
+//! ```
+//!
+//! On a linux machine, for 50k tasks:
+//!
+//! ```text
+//! without an index       924 ms for 50000 tasks
+//! with the index         355 ms for 50000 tasks
+//! one file changed       396 ms for 50000 tasks
+//! index removed          848 ms for 50000 tasks
+//! ```
+//!
+//! Runs without an index include writing it.
+use std::path::PathBuf;
+use std::time::Instant;
+
+use prmait::tasks::task::{Area, Task};
+use prmait::tasks::tasklist::TaskList;
+use time::OffsetDateTime;
+
+fn main() -> color_eyre::Result<()> {
+    let count: i64 = std::env::args()
+        .nth(1)
+        .map(|c| c.parse())
+        .transpose()?
+        .unwrap_or(50_000);
+
+    let dir: PathBuf = std::env::temp_dir().join(format!("prmait-index-bench-{count}"));
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    std::fs::create_dir_all(&dir)?;
+
+    let now = OffsetDateTime::now_utc();
+    for id in 0..count {
+        let task = Task {
+            id,
+            description: Some("a description that is about as long as a real one".to_owned()),
+            area: Some(Area::Work),
+            people: vec!["alice".to_owned()],
+            projects: vec!["prmait".to_owned()],
+            ..Task::new(format!("synthetic task number {id}"), now)
+        };
+        std::fs::write(
+            dir.join(format!("{id}.json")),
+            serde_json::to_string_pretty(&task)?,
+        )?;
+    }
+
+    let timed = |label: &str| -> color_eyre::Result<()> {
+        let started = Instant::now();
+        let tasks = TaskList::try_from(&dir)?;
+        println!(
+            "{label:<20}{:>6} ms for {} tasks",
+            started.elapsed().as_millis(),
+            tasks.0.len()
+        );
+        Ok(())
+    };
+
+    timed("without an index")?;
+    timed("with the index")?;
+    std::fs::write(dir.join("0.json"), std::fs::read(dir.join("1.json"))?)?;
+    timed("one file changed")?;
+    std::fs::remove_file(dir.join(prmait::index::INDEX_FILE_NAME))?;
+    timed("index removed")?;
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
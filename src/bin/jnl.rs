@@ -1,16 +1,37 @@
-use clap::{CommandFactory, Parser, Subcommand};
+use clap::{ArgGroup, CommandFactory, Parser, Subcommand, ValueEnum};
 use color_eyre::eyre::Result;
 use color_eyre::Report;
 use figment::providers::{Env, Format, Json};
 use figment::Figment;
 use prmait::effects::{EffectKind, EffectMachine};
-use prmait::journal::Mood;
-use prmait::{git, journal};
+use prmait::files::DraftDir;
+use prmait::journal::{Frontmatter, Mood, Period};
+use prmait::tasks::tasklist::TaskList;
+use prmait::completion::{self, Rule};
+use prmait::{context, git, journal, output};
+use std::collections::BTreeMap;
 use std::env;
-use std::{ffi::OsString, path::PathBuf, sync::Arc};
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
 use time::{format_description, OffsetDateTime};
 
 const DEFAULT_CONFIG_PATH: &str = "/home/a/.config/prmait/jnl.json";
+const DEFAULT_PASSPHRASE_ENV: &str = "JNL_PASSPHRASE";
+const DEFAULT_MAX_FILES_TO_OPEN: usize = 10;
+const COMPLETION_RULES: &[Rule] = &[
+    Rule {
+        subcommand: "new",
+        option: Some(('t', "tag")),
+        kind: "tags",
+    },
+    Rule {
+        subcommand: "new",
+        option: Some(('p', "people")),
+        kind: "people",
+    },
+];
 
 fn main() -> Result<()> {
     // error message management
@@ -23,7 +44,8 @@ fn main() -> Result<()> {
     let args = Args::parse();
 
     // forming config out of arguments
-    let config = Configs::try_from(&args.config.unwrap_or(PathBuf::from(DEFAULT_CONFIG_PATH)))?;
+    let config = Configs::try_from(&args.config.unwrap_or(PathBuf::from(DEFAULT_CONFIG_PATH)))?
+        .with_book(args.book.as_deref())?;
 
     // getting current time offset
     let time_offset = config
@@ -38,7 +60,10 @@ fn main() -> Result<()> {
         return Ok(());
     };
 
-    let efs = to_effect_machine(command, now, &config)?;
+    let efs = config
+        .git
+        .unwrap_or_default()
+        .restrict(to_effect_machine(command, now, &config, args.output)?);
     efs.run()?;
 
     Ok(())
@@ -48,78 +73,307 @@ fn to_effect_machine(
     journal_command: Commands,
     now: OffsetDateTime,
     config: &Configs,
+    output: output::Format,
 ) -> Result<EffectMachine, Report> {
     Ok(match journal_command {
-        Commands::Completions { shell } => {
-            let mut ef = EffectMachine::default();
-            ef.add(
-                EffectKind::GenerateShellCompletion(shell, Args::command()),
-                false,
-            );
-            ef
+        Commands::Completions { shell } => completion::generate(shell, Args::command(), COMPLETION_RULES),
+        Commands::Complete { kind } => {
+            // completing never prompts for the passphrase
+            if config.encryption.as_ref().is_some_and(|e| e.enabled && e.key_file.is_none())
+                && env::var(config.encryption.as_ref().and_then(|e| e.passphrase_env.as_deref()).unwrap_or(DEFAULT_PASSPHRASE_ENV)).is_err()
+            {
+                return Ok(EffectMachine::default());
+            }
+            let book = journal::Book::try_from((&config.journal_path()?, keyring(config)?.as_ref()))?;
+            completion::candidates(&match kind {
+                CompleteKind::Tags => completion::entry_tags(&book),
+                CompleteKind::People => completion::entry_people(&book),
+            })
         },
                 Commands::New {
                     entry,
                     tag,
                     mood,
+                    energy,
+                    emotion,
                     people,
+                    template,
+                    attach,
+                    location,
                 } => {
-                    let repo_root =  git::repo_root(&config.journal_path()?)?.to_string_lossy().into_owned();
-                    journal::effectors::new_entry(
-                        &journal::Entry {
-                            at: now,
-                            body: Arc::new(entry),
-                            tag,
-                            mood,
-                            people,
-                        },
-                        &config.journal_path()?,
+                    let journal_path = config.journal_path()?;
+                    let repo_root = repo_root(config)?;
+                    let keyring = keyring(config)?;
+                    let attachments = attachment_files(&attach)?;
+                    let context = config.context.clone().unwrap_or_default().capture(&env::current_dir()?, location);
+                    let template = config.template(template.as_deref())?;
+                    let template = Frontmatter {
+                        tags: [template.tags, tag].concat(),
+                        mood: mood.or(template.mood),
+                        energy: energy.or(template.energy),
+                        emotions: [template.emotions, emotion].concat(),
+                        people: [template.people, people].concat(),
+                        body: template.body,
+                    };
+                    match entry {
+                        Some(body) => journal::effectors::new_entry(
+                            &journal::Entry { context, ..Frontmatter { body, ..template }.into_entry(now)? },
+                            &attachments,
+                            &journal_path,
+                            &repo_root,
+                            keyring.as_ref(),
+                            now,
+                            &config.journal_file_formatting()?,
+                        )?,
+                        None => {
+                            // gone with the directory, whether the entry is saved or not
+                            let draft_dir = DraftDir::new("jnl-new-")?;
+                            let draft_path = draft_dir.path().join("entry.md");
+                            journal::effectors::compose_entry(
+                                &template,
+                                &draft_path,
+                                editor(env::var_os("EDITOR"))?,
+                            )?
+                            .run()?;
+                            journal::effectors::new_entry_from_draft(
+                                &draft_path,
+                                &attachments,
+                                &journal_path,
+                                &repo_root,
+                                keyring.as_ref(),
+                                context,
+                                now,
+                                &config.journal_file_formatting()?,
+                            )?
+                        }
+                    }
+                }
+                Commands::Import { from, path } => {
+                    let journal_path = config.journal_path()?;
+                    let repo_root = repo_root(config)?;
+                    let keyring = keyring(config)?;
+                    journal::effectors::import_entries(
+                        journal::import::read_entries(from, &path, now.offset())?,
+                        &journal::Book::try_from((&journal_path, keyring.as_ref()))?,
+                        &journal_path,
                         &repo_root,
-                        now,
+                        keyring.as_ref(),
                         &config.journal_file_formatting()?,
                     )?
                 }
-                Commands::List => {
+                Commands::Attach { entry, files } => {
+                    let journal_path = config.journal_path()?;
+                    let repo_root = repo_root(config)?;
+                    let keyring = keyring(config)?;
+                    journal::effectors::attach_files(
+                        &journal::Book::try_from((&journal_path, keyring.as_ref()))?,
+                        &entry,
+                        &attachment_files(&files)?,
+                        &repo_root,
+                        keyring.as_ref(),
+                    )?
+                }
+                Commands::Export { entry, to } => {
+                    let journal_path = config.journal_path()?;
+                    let keyring = keyring(config)?;
+                    journal::effectors::export_attachments(
+                        &journal::Book::try_from((&journal_path, keyring.as_ref()))?,
+                        &entry,
+                        &to,
+                        keyring.as_ref(),
+                    )?
+                }
+                Commands::Show { entry } => journal::effectors::show_entry(
+                    &journal::Book::try_from((&config.journal_path()?, keyring(config)?.as_ref()))?,
+                    &entry,
+                    config.task_path.as_ref().map(TaskList::try_from).transpose()?.as_ref(),
+                    &time::format_description::parse_borrowed::<2>("[year]-[month]-[day] [hour]:[minute]")?,
+                )?,
+                Commands::OnThisDay => journal::effectors::on_this_day(
+                    &journal::Book::try_from((&config.journal_path()?, keyring(config)?.as_ref()))?,
+                    now,
+                    &time::format_description::parse_borrowed::<2>("[year]-[month]-[day] [hour]:[minute]")?,
+                )?,
+                Commands::Review { week: _, month } => journal::effectors::review(
+                    &journal::Book::try_from((&config.journal_path()?, keyring(config)?.as_ref()))?,
+                    if month { Period::Month } else { Period::Week },
+                    now,
+                    &time::format_description::parse_borrowed::<2>("[year]-[month]-[day] [hour]:[minute]")?,
+                )?,
+                Commands::Stats => journal::effectors::mood_stats(&journal::Book::try_from((&config.journal_path()?, keyring(config)?.as_ref()))?),
+                Commands::Doctor(DoctorCommands::Migrate) => {
+                    let journal_path = config.journal_path()?;
+                    let repo_root = repo_root(config)?;
+                    journal::effectors::migrate_entries(&journal_path, &repo_root, keyring(config)?.as_ref())?
+                }
+                Commands::List { all_books: false } => {
                     let format = time::format_description::parse_borrowed::<2>("[year]-[month]-[day] [hour]:[minute]")?;
                     journal::effectors::list_entries(
-                                    &journal::Book::try_from(&config.journal_path()?)?,
+                                    &journal::Book::try_from((&config.journal_path()?, keyring(config)?.as_ref()))?,
+                                    output,
                                     &format,
                                 )?
                 },
+                Commands::List { all_books: true } => {
+                    let format = time::format_description::parse_borrowed::<2>("[year]-[month]-[day] [hour]:[minute]")?;
+                    let keyring = keyring(config)?;
+                    let books = config
+                        .books
+                        .clone()
+                        .ok_or(Error::UnsetConfiguration("books".to_owned()))?
+                        .into_iter()
+                        .map(|(name, book)| Ok((name, journal::Book::try_from((&book.path, keyring.as_ref()))?)))
+                        .collect::<Result<Vec<_>>>()?;
+                    journal::effectors::list_books(
+                        &books.iter().map(|(name, book)| (name.as_str(), book)).collect::<Vec<_>>(),
+                        output,
+                        &format,
+                    )?
+                },
                 Commands::Edit(edit_type) => {
-                    let repo_root = git::repo_root(&config.journal_path()?)?.to_string_lossy().into_owned();
-                    match edit_type {
-                        JournalEditCommands::Last => journal::effectors::edit_last_entry(
-                            &config.journal_path()?,
-                            &journal::Book::try_from(&config.journal_path()?)?,
+                    let journal_path = config.journal_path()?;
+                    let repo_root = repo_root(config)?;
+                    let keyring = keyring(config)?;
+                    let book = journal::Book::try_from((&journal_path, keyring.as_ref()))?;
+                    let Some(entries) = entries_to_edit(&edit_type, &book, config)? else {
+                        let mut efs = EffectMachine::default();
+                        efs.add(EffectKind::PrintToStdOut("nothing was opened".to_owned()), false);
+                        return Ok(efs);
+                    };
+                    match (keyring, edit_type) {
+                        (Some(keyring), _) => edit_sealed(&entries, &journal_path, &repo_root, &keyring)?,
+                        (None, JournalEditCommands::Last) => journal::effectors::edit_last_entry(
+                            &journal_path,
+                            &book,
                             &repo_root,
                             editor(env::var_os("EDITOR"))?,
                         )?,
-                        JournalEditCommands::All => journal::effectors::edit_all_entries(
+                        (None, JournalEditCommands::All) => journal::effectors::edit_all_entries(
                             editor(env::var_os("EDITOR"))?,
-                            &journal::Book::try_from(&config.journal_path()?)?,
+                            &book,
                             &repo_root,
                         )?,
-                        JournalEditCommands::Specific { item } => {
-                            journal::effectors::edit_specific_entry(
-                                &config.journal_path()?,
-                                &item,
-                                &journal::Book::try_from(&config.journal_path()?)?,
+                        (None, JournalEditCommands::Specific { .. } | JournalEditCommands::Pick { .. }) => {
+                            journal::effectors::edit_entries(
+                                &journal_path,
+                                &entries,
                                 &repo_root,
                                 editor(env::var_os("EDITOR"))?,
                             )?
                         }
                     }
                 }
-                // JournalCommands::Delete => journal::handlers::delete_interactive(
-                //     &config.journal_path()?,
-                //     20,
-                //     journal::Book::try_from(&config.journal_path()?)?,
-                //     &well_known::Rfc3339,
-                // )?,
+                Commands::Delete { id } => {
+                    let journal_path = config.journal_path()?;
+                    let repo_root = repo_root(config)?;
+                    let book = journal::Book::try_from((&journal_path, keyring(config)?.as_ref()))?;
+                    match id {
+                        Some(id) => journal::effectors::delete_entry(&book, &id, &repo_root)?,
+                        None => journal::effectors::delete_interactive(
+                            20,
+                            &book,
+                            &repo_root,
+                            &time::format_description::parse_borrowed::<2>("[year]-[month]-[day] [hour]:[minute]")?,
+                        )?,
+                    }
+                }
             })
 }
 
+/// The root of the repository the journal is in, or the journal itself when git is off for it.
+fn repo_root(config: &Configs) -> Result<String> {
+    let journal_path = config.journal_path()?;
+    match git::repo_root(&journal_path) {
+        Ok(root) => Ok(root.to_string_lossy().into_owned()),
+        Err(_e) if config.git == Some(git::Behaviour::Off) => {
+            Ok(journal_path.to_string_lossy().into_owned())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn attachment_files(paths: &[PathBuf]) -> Result<Vec<journal::AttachmentFile>> {
+    Ok(paths
+        .iter()
+        .map(journal::AttachmentFile::try_from)
+        .collect::<Result<_, _>>()?)
+}
+
+/// The entries that the edit command is about, or nothing if the user changed their mind.
+fn entries_to_edit<'a>(
+    edit_type: &JournalEditCommands,
+    book: &'a journal::Book,
+    config: &Configs,
+) -> Result<Option<Vec<&'a journal::EntryDescription>>> {
+    let entries: Vec<&journal::EntryDescription> = match edit_type {
+        JournalEditCommands::Last => book.entries.last().into_iter().collect(),
+        JournalEditCommands::All => book.entries.iter().collect(),
+        JournalEditCommands::Specific { item } => book
+            .entries
+            .iter()
+            .filter(|x| x.file_name.contains(item.as_str()))
+            .collect(),
+        JournalEditCommands::Pick { multiple } => journal::effectors::pick_entries(
+            book,
+            *multiple,
+            60,
+            &time::format_description::parse_borrowed::<2>("[year]-[month]-[day] [hour]:[minute]")?,
+        )?,
+    };
+    if !journal::effectors::confirm_opening(
+        entries.len(),
+        config.max_files_to_open.unwrap_or(DEFAULT_MAX_FILES_TO_OPEN),
+    )? {
+        return Ok(None);
+    }
+    Ok(Some(entries))
+}
+
+/// Entries of an encrypted journal are edited through decrypted drafts, in a directory of their
+/// own that is shredded as soon as they are read back, or when anything fails.
+fn edit_sealed(
+    entries: &[&journal::EntryDescription],
+    journal_path: &Path,
+    repo_root: &str,
+    keyring: &journal::Keyring,
+) -> Result<EffectMachine, Report> {
+    let draft_dir = DraftDir::new("jnl-edit-")?;
+    let (efs, drafts) = journal::effectors::edit_sealed_entries(
+        entries,
+        journal_path,
+        &draft_dir,
+        editor(env::var_os("EDITOR"))?,
+    )?;
+    efs.run()?;
+    Ok(journal::effectors::save_sealed_drafts(&drafts, repo_root, keyring)?)
+}
+
+/// The keyring of an encrypted journal, the secret comes from the key file, the passphrase
+/// environment variable, or a prompt, in that order.
+fn keyring(config: &Configs) -> Result<Option<journal::Keyring>> {
+    let Some(encryption) = config.encryption.clone().filter(|e| e.enabled) else {
+        return Ok(None);
+    };
+    if let Some(key_file) = encryption.key_file {
+        return Ok(Some(journal::Keyring::from_key_file(&key_file)?));
+    }
+    let passphrase = match env::var(
+        encryption
+            .passphrase_env
+            .as_deref()
+            .unwrap_or(DEFAULT_PASSPHRASE_ENV),
+    ) {
+        Ok(passphrase) => passphrase,
+        Err(_e) => dialoguer::Password::new()
+            .with_prompt("journal passphrase")
+            .interact()?,
+    };
+    if passphrase.is_empty() {
+        return Err(journal::Error::KeyIsEmpty.into());
+    }
+    Ok(Some(journal::Keyring::new(passphrase.into_bytes())))
+}
+
 fn editor(extractor: Option<OsString>) -> Result<String> {
     let editor = extractor.ok_or(Report::msg("editor variable is not specified"))?;
     if editor.is_empty() {
@@ -134,12 +388,20 @@ fn editor(extractor: Option<OsString>) -> Result<String> {
 }
 
 #[derive(Clone, Debug, Parser)]
-#[command(version,about="Personal journaling, like never before", long_about = None, arg_required_else_help = true)]
+#[command(name = "jnl", version,about="Personal journaling, like never before", long_about = None, arg_required_else_help = true)]
 pub struct Args {
     /// Sets a custom config file
     #[arg(short, long, value_name = "FILE")]
     pub config: Option<PathBuf>,
 
+    /// The book in the configuration to work on, instead of the default one
+    #[arg(short, long, global = true)]
+    pub book: Option<String>,
+
+    /// How lists are written, colours are left out of every format when NO_COLOR is set
+    #[arg(short, long, global = true, value_enum, default_value_t)]
+    pub output: output::Format,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
@@ -153,24 +415,108 @@ pub enum Commands {
     },
     /// New Entry
     New {
-        entry: String,
+        /// The body of the entry, opens the editor on the template if not given [OPTIONAL]
+        entry: Option<String>,
         /// Tags that apply to this item [OPTIONAL]
         #[arg(short = 't', long)]
         tag: Vec<String>,
-        /// Mood associated with this entry [Required]
-        #[arg(short = 'm', long)]
-        mood: Mood,
+        /// Mood associated with this entry, from -5 to 5, or good, bad and neutral [OPTIONAL]
+        #[arg(short = 'm', long, value_parser = Mood::parse_score, allow_negative_numbers = true)]
+        mood: Option<i8>,
+        /// Energy level at the time of this entry, from 0 to 10 [OPTIONAL]
+        #[arg(short = 'e', long, value_parser = Mood::parse_energy)]
+        energy: Option<u8>,
+        /// Emotions felt at the time of this entry [OPTIONAL]
+        #[arg(short = 'E', long)]
+        emotion: Vec<String>,
         /// People related to this entry  [OPTIONAL]
         #[arg(short = 'p', long)]
         people: Vec<String>,
+        /// Name of the template in the configuration to start the entry from [OPTIONAL]
+        #[arg(short = 'T', long)]
+        template: Option<String>,
+        /// Files to attach to this entry, like photos or voice memos [OPTIONAL]
+        #[arg(short = 'a', long)]
+        attach: Vec<PathBuf>,
+        /// Where this entry is written, instead of the location in the configuration [OPTIONAL]
+        #[arg(short = 'l', long)]
+        location: Option<String>,
+    },
+    /// Attach files to an existing entry
+    Attach {
+        /// A part of the file name of the entry, that matches only that entry
+        entry: String,
+        /// The files to attach
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Write the files attached to an entry into a directory, decrypted if the journal is
+    /// encrypted
+    Export {
+        /// A part of the file name of the entry, that matches only that entry
+        entry: String,
+        /// Where to write the files [DEFAULT: the current directory]
+        #[arg(short = 't', long, default_value = ".")]
+        to: PathBuf,
+    },
+    /// Import entries from other journaling tools, skipping the ones at a moment the journal
+    /// already has an entry at
+    Import {
+        /// The format of the entries being imported
+        #[arg(short = 'f', long, value_enum)]
+        from: journal::import::Format,
+        /// The exported file, or the directory of markdown files
+        path: PathBuf,
     },
     /// List of entries
-    List,
+    List {
+        /// Every book in the configuration, the entries merged from oldest to newest
+        #[arg(long, conflicts_with = "book")]
+        all_books: bool,
+    },
+    /// Show an entry, with the tasks and entries it links to and the entries that link to it
+    Show {
+        /// A part of the file name of the entry, that matches only that entry
+        entry: String,
+    },
+    /// Summary of the moods over all entries
+    Stats,
+    /// Entries written on this day in previous years
+    #[command(name = "onthisday")]
+    OnThisDay,
+    /// Every entry of this week or this month, day by day
+    #[command(group(ArgGroup::new("period").required(true)))]
+    Review {
+        /// From monday to sunday
+        #[arg(long, group = "period")]
+        week: bool,
+        #[arg(long, group = "period")]
+        month: bool,
+    },
     /// edit commands
     #[command(subcommand)]
     Edit(JournalEditCommands),
-    // interactively delete an entry
-    // Delete,
+    /// Maintenance of the journal itself
+    #[command(subcommand)]
+    Doctor(DoctorCommands),
+    /// Delete an entry, picking it interactively unless an id is given
+    Delete {
+        /// A part of the file name of the entry, that matches only that entry
+        #[arg(long)]
+        id: Option<String>,
+    },
+    /// Candidates for the completion scripts, one per line with its description after a tab
+    #[command(name = completion::COMPLETE_COMMAND, hide = true)]
+    Complete {
+        #[arg(value_enum)]
+        kind: CompleteKind,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ValueEnum)]
+pub enum CompleteKind {
+    Tags,
+    People,
 }
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -182,6 +528,18 @@ pub enum JournalEditCommands {
     All,
     /// Open only strings matching the given entry
     Specific { item: String },
+    /// Pick the entries to open from a fuzzy searchable list
+    Pick {
+        /// Pick any number of entries instead of one, each search picks or unpicks one more
+        #[arg(short, long)]
+        multiple: bool,
+    },
+}
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Subcommand)]
+pub enum DoctorCommands {
+    /// Rewrite every entry written with an older schema to the current one
+    Migrate,
 }
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -189,6 +547,30 @@ pub struct Configs {
     pub time_offset: Option<(i8, i8, i8)>,
     pub path: Option<PathBuf>,
     pub file_name_format: Option<String>,
+    /// Whether changes are committed, and pushed, sync if not set
+    pub git: Option<git::Behaviour>,
+    /// Separate journals, each with its own directory
+    pub books: Option<BTreeMap<String, BookConfigs>>,
+    /// The book used when none is given, the top level `path` is used if this is not set either
+    pub default_book: Option<String>,
+    pub templates: Option<BTreeMap<String, Frontmatter>>,
+    pub encryption: Option<journal::encryption::Config>,
+    /// The tasks that entries can link to
+    pub task_path: Option<PathBuf>,
+    /// Opening more entries than this at once asks for confirmation first, 10 if not set
+    pub max_files_to_open: Option<usize>,
+    /// What is recorded about where new entries are written, everything if not set
+    pub context: Option<context::Config>,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BookConfigs {
+    pub path: PathBuf,
+    /// The top level `file_name_format` if not set
+    pub file_name_format: Option<String>,
+    /// The top level `git` if not set
+    pub git: Option<git::Behaviour>,
 }
 
 impl TryFrom<PathBuf> for Configs {
@@ -221,9 +603,32 @@ pub enum Error {
     UnsetConfiguration(String),
     #[error("File format descriptor for journal is not valid: {0}.")]
     TheFormatIsNotValid(#[from] time::error::InvalidFormatDescription),
+    #[error("template {0} is not defined in the configuration.")]
+    TemplateIsNotDefined(String),
+    #[error("book {0} is not defined in the configuration.")]
+    BookIsNotDefined(String),
 }
 
 impl Configs {
+    /// The configuration with the path, file name format and git behaviour of the named book, or
+    /// of the default book when no name is given.
+    pub fn with_book(self, name: Option<&str>) -> Result<Self, Box<Error>> {
+        let Some(name) = name.or(self.default_book.as_deref()) else {
+            return Ok(self);
+        };
+        let book = self
+            .books
+            .as_ref()
+            .and_then(|books| books.get(name))
+            .cloned()
+            .ok_or_else(|| Box::new(Error::BookIsNotDefined(name.to_owned())))?;
+        Ok(Self {
+            path: Some(book.path),
+            file_name_format: book.file_name_format.or(self.file_name_format.clone()),
+            git: book.git.or(self.git),
+            ..self
+        })
+    }
     pub fn journal_path(&self) -> Result<PathBuf, Error> {
         self.clone().path.ok_or(Error::DirDoesNotExist)
     }
@@ -235,4 +640,15 @@ impl Configs {
                 .ok_or(Error::UnsetConfiguration("file_name_format".to_owned()))?,
         )?)
     }
+    /// The named template, or the one named `default` if there is one, when no name is given.
+    pub fn template(&self, name: Option<&str>) -> Result<Frontmatter, Box<Error>> {
+        let templates = self.templates.clone().unwrap_or_default();
+        match name {
+            Some(name) => templates
+                .get(name)
+                .cloned()
+                .ok_or_else(|| Box::new(Error::TemplateIsNotDefined(name.to_owned()))),
+            None => Ok(templates.get("default").cloned().unwrap_or_default()),
+        }
+    }
 }
@@ -1,19 +1,76 @@
-use clap::{arg, CommandFactory, Parser, Subcommand};
+use clap::{arg, ArgGroup, CommandFactory, Parser, Subcommand, ValueEnum};
 use color_eyre::eyre::Result;
 use color_eyre::Report;
 use figment::providers::{Env, Format, Json};
 use figment::Figment;
-use prmait::effects::{EffectKind, EffectMachine};
+use prmait::completion::{self, Rule};
+use prmait::effects::EffectMachine;
+use prmait::files::DraftDir;
+use prmait::journal::Period;
+use prmait::tasks::bulk::Action;
 use prmait::tasks::effectors::{mark_task_as, tasks_by_state, todays_task};
-use prmait::tasks::task::{Area, State, Task};
+use prmait::tasks::focus;
+use prmait::tasks::query::Query;
+use prmait::tasks::recurrence::Recurrence;
+use prmait::tasks::task::{Area, Priority, State, Task};
 use prmait::tasks::tasklist::TaskList;
-use prmait::{git, tasks, timeutils};
+use prmait::tasks::urgency::Sort;
+use prmait::{context, git, journal, output, tasks, timeutils};
+use std::collections::BTreeMap;
 use std::env;
+use std::ffi::OsString;
 use std::path::PathBuf;
 use time::format_description::{self, well_known};
 use time::OffsetDateTime;
 
 const DEFAULT_CONFIG_PATH: &str = "/home/a/.config/prmait/tsk.json";
+const COMPLETION_RULES: &[Rule] = &[
+    Rule {
+        subcommand: "done",
+        option: None,
+        kind: "open-tasks",
+    },
+    Rule {
+        subcommand: "backlog",
+        option: None,
+        kind: "open-tasks",
+    },
+    Rule {
+        subcommand: "abandon",
+        option: None,
+        kind: "open-tasks",
+    },
+    Rule {
+        subcommand: "show",
+        option: None,
+        kind: "open-tasks",
+    },
+    Rule {
+        subcommand: "start",
+        option: None,
+        kind: "open-tasks",
+    },
+    Rule {
+        subcommand: "focus",
+        option: None,
+        kind: "open-tasks",
+    },
+    Rule {
+        subcommand: "edit",
+        option: None,
+        kind: "open-tasks",
+    },
+    Rule {
+        subcommand: "new",
+        option: Some(('p', "projects")),
+        kind: "projects",
+    },
+    Rule {
+        subcommand: "new",
+        option: Some(('P', "people")),
+        kind: "people",
+    },
+];
 
 fn main() -> Result<()> {
     // error message management
@@ -50,12 +107,90 @@ fn main() -> Result<()> {
         return Ok(());
     };
 
-    let efs = to_effect_machine(command, now, config, time_offset, project, &task_dir)?;
+    if let Commands::Focus {
+        id: Some(id),
+        minutes,
+        break_minutes,
+        cycles,
+        summary: false,
+    } = command
+    {
+        return focus(
+            id,
+            minutes,
+            break_minutes,
+            cycles,
+            &config.focus.unwrap_or_default(),
+            time_offset,
+            &task_dir,
+        );
+    }
+
+    let efs = to_effect_machine(
+        command,
+        now,
+        config,
+        time_offset,
+        project,
+        &task_dir,
+        args.output,
+    )?;
     efs.run()?;
 
     Ok(())
 }
 
+/// Counts down the sessions of focus on the task and the breaks between them, logging every
+/// session to the task as soon as it ends. It stops early once a session or a break is interrupted.
+fn focus(
+    id: i64,
+    minutes: u16,
+    break_minutes: u16,
+    cycles: u16,
+    focus_config: &focus::Config,
+    time_offset: time::UtcOffset,
+    task_dir: &PathBuf,
+) -> Result<()> {
+    let id = TaskList::try_from(task_dir)?.find(id)?.task.id;
+    let repo_root = git::repo_root(task_dir)?.to_string_lossy().into_owned();
+    let current_time = || OffsetDateTime::now_utc().to_offset(time_offset);
+    for cycle in 1..=cycles {
+        let task_list = TaskList::try_from(task_dir)?;
+        let the_task_description = task_list.find(id)?;
+        let start = current_time();
+        let ending = focus::countdown(
+            &format!("🍅 {cycle}/{cycles} {}", the_task_description.task.title),
+            time::Duration::minutes(i64::from(minutes)),
+        )?;
+        let session = focus::Session {
+            start,
+            end: current_time(),
+            planned_minutes: minutes,
+            interrupted: ending == focus::Ending::Interrupted,
+        };
+        tasks::effectors::log_focus_session(
+            task_dir,
+            the_task_description,
+            session,
+            &repo_root,
+            focus_config,
+        )?
+        .run()?;
+        if session.interrupted || cycle == cycles || break_minutes == 0 {
+            break;
+        }
+        let ending = focus::countdown(
+            "☕ break",
+            time::Duration::minutes(i64::from(break_minutes)),
+        )?;
+        if ending == focus::Ending::Interrupted {
+            break;
+        }
+        focus_config.notification("the break is over").apply()?;
+    }
+    Ok(())
+}
+
 fn to_effect_machine(
     command: Commands,
     now: OffsetDateTime,
@@ -63,6 +198,7 @@ fn to_effect_machine(
     time_offset: time::UtcOffset,
     project: Option<String>,
     task_dir: &PathBuf,
+    output: output::Format,
 ) -> Result<EffectMachine, Report> {
     Ok(match command {
         Commands::New {
@@ -73,6 +209,10 @@ fn to_effect_machine(
             deadline,
             best_starting_time,
             projects,
+            location,
+            parent,
+            repeat,
+            priority,
         } => {
             let end = deadline
                 .map(|s| timeutils::parse_date(&s, time_offset))
@@ -91,16 +231,23 @@ fn to_effect_machine(
             };
 
             let t = Task {
-                id: now.unix_timestamp(),
-                time_created: now,
-                state_log: vec![State::ToDo(now)],
-                title,
                 description,
                 area,
                 people: people.unwrap_or(vec![]),
                 projects,
                 start,
                 end,
+                context: config
+                    .context
+                    .clone()
+                    .unwrap_or_default()
+                    .capture(&env::current_dir()?, location),
+                parent: parent
+                    .map(|p| TaskList::try_from(task_dir).and_then(|l| Ok(l.find(p)?.task.id)))
+                    .transpose()?,
+                recurrence: repeat,
+                priority,
+                ..Task::new(title, now)
             };
             let repo_root = git::repo_root(&config.task_path()?)?
                 .to_string_lossy()
@@ -113,57 +260,163 @@ fn to_effect_machine(
                 &config.task_file_formatting()?,
             )?
         }
-        Commands::List(task_list_command) => {
-            let tasklist = TaskList::try_from(task_dir)?;
-            match task_list_command {
-                TaskListCommand::Today => {
-                    todays_task(tasklist, now.date(), &project, now, &well_known::Rfc3339)?
-                }
-                TaskListCommand::Todo => tasks_by_state(
+        Commands::List {
+            sort,
+            condition,
+            query,
+            which,
+        } => {
+            let tasklist = match sort {
+                Sort::Created => TaskList::try_from(task_dir)?,
+                Sort::Urgency => tasks::urgency::sorted(
+                    TaskList::try_from(task_dir)?,
+                    now,
+                    &config.urgency.clone().unwrap_or_default(),
+                ),
+            };
+            let saved = query
+                .map(|name| {
+                    config
+                        .queries
+                        .as_ref()
+                        .and_then(|queries| queries.get(&name))
+                        .ok_or_else(|| Report::msg(format!("no query is saved as {name}")))
+                })
+                .transpose()?;
+            let query = [saved.map(String::as_str), condition.as_deref()]
+                .into_iter()
+                .flatten()
+                .map(|q| Query::parse(q, time_offset))
+                .reduce(|left, right| Ok(Query::And(Box::new(left?), Box::new(right?))))
+                .transpose()?;
+            // a query says which projects it is about
+            let project = if query.is_some() { None } else { project };
+            let (query, project) = (query.as_ref(), &project);
+            match which {
+                Some(TaskListCommand::Today) => todays_task(
+                    tasklist,
+                    now.date(),
+                    project,
+                    query,
+                    now,
+                    output,
+                    &well_known::Rfc3339,
+                )?,
+                Some(TaskListCommand::Todo) => tasks_by_state(
                     tasklist,
                     |x| matches!(x, &State::ToDo(_)),
-                    &project,
+                    project,
+                    query,
                     now,
+                    output,
                     &well_known::Rfc3339,
                 )?,
-                TaskListCommand::Done => tasks_by_state(
+                Some(TaskListCommand::Done) => tasks_by_state(
                     tasklist,
                     |x| matches!(x, &State::Done(_)),
-                    &project,
+                    project,
+                    query,
                     now,
+                    output,
                     &well_known::Rfc3339,
                 )?,
-                TaskListCommand::Abandoned => tasks_by_state(
+                Some(TaskListCommand::Abandoned) => tasks_by_state(
                     tasklist,
                     |x| matches!(x, State::Abandoned(_, _)),
-                    &project,
+                    project,
+                    query,
                     now,
+                    output,
                     &well_known::Rfc3339,
                 )?,
-                TaskListCommand::Backlogged => tasks_by_state(
+                Some(TaskListCommand::Backlogged) => tasks_by_state(
                     tasklist,
                     |x| matches!(x, State::Backlog(_)),
-                    &project,
+                    project,
+                    query,
+                    now,
+                    output,
+                    &well_known::Rfc3339,
+                )?,
+                None => tasks_by_state(
+                    tasklist,
+                    |_| true,
+                    project,
+                    query,
                     now,
+                    output,
                     &well_known::Rfc3339,
                 )?,
             }
         }
-        Commands::Done { id } => {
+        Commands::Start { id } => {
+            let repo_root = git::repo_root(task_dir)?.to_string_lossy().into_owned();
+            tasks::effectors::start_task(
+                task_dir,
+                &TaskList::try_from(task_dir)?,
+                id,
+                &repo_root,
+                now,
+            )?
+        }
+        Commands::Stop => {
+            let repo_root = git::repo_root(task_dir)?.to_string_lossy().into_owned();
+            tasks::effectors::stop_task(task_dir, &TaskList::try_from(task_dir)?, &repo_root, now)?
+        }
+        Commands::Report { week: _, month } => tasks::effectors::time_report(
+            &TaskList::try_from(task_dir)?,
+            if month { Period::Month } else { Period::Week },
+            now,
+            output,
+        )?,
+        // focusing on a task is a loop of its own that runs in `main`, only the summary is left
+        Commands::Focus { .. } => {
+            tasks::effectors::focus_summary(&TaskList::try_from(task_dir)?, now, output)?
+        }
+        Commands::Next { count } => tasks::effectors::next_tasks(
+            &TaskList::try_from(task_dir)?,
+            count,
+            &project,
+            now,
+            &config.urgency.clone().unwrap_or_default(),
+            output,
+            &well_known::Rfc3339,
+        )?,
+        Commands::Done { id, recursive } => {
             let task_list = TaskList::try_from(task_dir)?;
             let repo_root = git::repo_root(&config.task_path()?)?
                 .to_string_lossy()
                 .into_owned();
-            mark_task_as(task_dir, &task_list, &State::Done(now), &repo_root, &id)?
+            mark_task_as(
+                task_dir,
+                &task_list,
+                &State::Done(now),
+                &repo_root,
+                &id,
+                recursive,
+                &config.task_file_formatting()?,
+            )?
         }
         Commands::Backlog { id } => {
             let task_list = TaskList::try_from(task_dir)?;
             let repo_root = git::repo_root(&config.task_path()?)?
                 .to_string_lossy()
                 .into_owned();
-            mark_task_as(task_dir, &task_list, &State::Backlog(now), &repo_root, &id)?
+            mark_task_as(
+                task_dir,
+                &task_list,
+                &State::Backlog(now),
+                &repo_root,
+                &id,
+                false,
+                &config.task_file_formatting()?,
+            )?
         }
-        Commands::Abandon { id, content } => {
+        Commands::Abandon {
+            id,
+            recursive,
+            content,
+        } => {
             let task_list = TaskList::try_from(task_dir)?;
             let repo_root = git::repo_root(&config.task_path()?)?
                 .to_string_lossy()
@@ -174,6 +427,8 @@ fn to_effect_machine(
                 &State::Abandoned(now, content),
                 &repo_root,
                 &id,
+                recursive,
+                &config.task_file_formatting()?,
             )?
         }
         Commands::Todo { id } => {
@@ -181,26 +436,191 @@ fn to_effect_machine(
             let repo_root = git::repo_root(&config.task_path()?)?
                 .to_string_lossy()
                 .into_owned();
-            mark_task_as(task_dir, &task_list, &State::ToDo(now), &repo_root, &id)?
+            mark_task_as(
+                task_dir,
+                &task_list,
+                &State::ToDo(now),
+                &repo_root,
+                &id,
+                false,
+                &config.task_file_formatting()?,
+            )?
+        }
+        Commands::Bulk { filter, all } => {
+            // an empty draft is still opened, new tasks can be added to it
+            let selected =
+                tasks::bulk::select(&TaskList::try_from(task_dir)?, filter.as_deref(), all);
+            // gone with the directory, whether the actions are applied or not
+            let draft_dir = DraftDir::new("tsk-bulk-")?;
+            let draft_path = draft_dir.path().join("tasks.tsv");
+            tasks::effectors::open_bulk_editor(
+                &selected,
+                &draft_path,
+                editor(env::var_os("EDITOR"))?,
+            )?
+            .run()?;
+            let repo_root = git::repo_root(task_dir)?.to_string_lossy().into_owned();
+            tasks::effectors::apply_bulk_actions(
+                task_dir,
+                &selected,
+                &draft_path,
+                &repo_root,
+                now,
+                config
+                    .context
+                    .clone()
+                    .unwrap_or_default()
+                    .capture(&env::current_dir()?, None)
+                    .as_ref(),
+                &config.task_file_formatting()?,
+            )?
+        }
+        Commands::Edit {
+            id,
+            title,
+            description,
+            area,
+            deadline,
+            start,
+            add_project,
+            remove_project,
+            add_person,
+            remove_person,
+            repeat,
+            priority,
+            editor: with_editor,
+        } => {
+            let task_list = TaskList::try_from(task_dir)?;
+            let the_task_description = task_list.find(id)?;
+            let repo_root = git::repo_root(task_dir)?.to_string_lossy().into_owned();
+            let non_empty = |v: String| (!v.is_empty()).then_some(v);
+            let actions: Vec<Action> = [
+                title.map(Action::Title),
+                description.map(|d| Action::Description(non_empty(d))),
+                area.map(|a| Action::Area(Some(a))),
+                deadline.map(|d| Action::Deadline(non_empty(d))),
+                start.map(|s| Action::Start(non_empty(s))),
+                repeat
+                    .map(|r| non_empty(r).map(|r| r.parse()).transpose())
+                    .transpose()?
+                    .map(Action::Repeat),
+                priority
+                    .map(|p| non_empty(p).map(|p| p.parse()).transpose())
+                    .transpose()?
+                    .map(Action::Priority),
+            ]
+            .into_iter()
+            .flatten()
+            .chain(add_project.into_iter().map(Action::AddProject))
+            .chain(remove_project.into_iter().map(Action::RemoveProject))
+            .chain(add_person.into_iter().map(Action::AddPerson))
+            .chain(remove_person.into_iter().map(Action::RemovePerson))
+            .collect();
+
+            if with_editor || actions.is_empty() {
+                let draft_dir = DraftDir::new("tsk-edit-")?;
+                let draft_path = draft_dir.path().join("task.md");
+                tasks::effectors::open_task_sheet(
+                    &the_task_description.task,
+                    &draft_path,
+                    editor(env::var_os("EDITOR"))?,
+                )?
+                .run()?;
+                tasks::effectors::edit_task_from_sheet(
+                    task_dir,
+                    the_task_description,
+                    &draft_path,
+                    &repo_root,
+                    now,
+                )?
+            } else {
+                tasks::effectors::edit_task(
+                    task_dir,
+                    the_task_description,
+                    &actions,
+                    &repo_root,
+                    now,
+                )?
+            }
+        }
+        Commands::Dep(dep_command) => {
+            let task_list = TaskList::try_from(task_dir)?;
+            let repo_root = git::repo_root(task_dir)?.to_string_lossy().into_owned();
+            match dep_command {
+                DepCommands::Add { id, on } => {
+                    tasks::effectors::add_dependencies(task_dir, &task_list, id, &on, &repo_root)?
+                }
+                DepCommands::Rm { id, on } => tasks::effectors::remove_dependencies(
+                    task_dir, &task_list, id, &on, &repo_root,
+                )?,
+            }
+        }
+        Commands::Graph { dot } => {
+            tasks::effectors::dependency_graph(&TaskList::try_from(task_dir)?, dot)?
+        }
+        Commands::Show { id } => {
+            let mut books = vec![];
+            let mut unreadable = 0;
+            for journal_path in config
+                .journal_path
+                .iter()
+                .chain(config.journal_books.iter().flatten())
+            {
+                let (book, skipped) = journal::Book::readable(journal_path)?;
+                books.push(book);
+                unreadable += skipped.len();
+            }
+            tasks::effectors::show_task(
+                &TaskList::try_from(task_dir)?,
+                id,
+                &books,
+                unreadable,
+                now,
+                &well_known::Rfc3339,
+            )?
+        }
+        Commands::Doctor(DoctorCommands::Migrate) => {
+            let repo_root = git::repo_root(task_dir)?.to_string_lossy().into_owned();
+            tasks::effectors::migrate_tasks(task_dir, &repo_root)?
         }
         Commands::Completions { shell } => {
-            let mut ef = EffectMachine::default();
-            ef.add(
-                EffectKind::GenerateShellCompletion(shell, Args::command()),
-                false,
-            );
-            ef
+            completion::generate(shell, Args::command(), COMPLETION_RULES)
+        }
+        Commands::Complete { kind } => {
+            let task_list = TaskList::try_from(task_dir)?;
+            completion::candidates(&match kind {
+                CompleteKind::OpenTasks => completion::open_tasks(&task_list),
+                CompleteKind::Projects => completion::task_projects(&task_list),
+                CompleteKind::People => completion::task_people(&task_list),
+            })
         }
     })
 }
 
+fn editor(extractor: Option<OsString>) -> Result<String> {
+    let editor = extractor.ok_or(Report::msg("editor variable is not specified"))?;
+    if editor.is_empty() {
+        return Err(Report::msg("editor variable is not specified"));
+    };
+    match editor.into_string() {
+        Ok(s) => Ok(s),
+        Err(e) => Err(Report::msg(format!(
+            "could not convert file name to string: {e:?}"
+        ))),
+    }
+}
+
 #[derive(Clone, Debug, Parser)]
-#[command(version,about="The tusky task manager", long_about = None, arg_required_else_help = true)]
+#[command(name = "tsk", version,about="The tusky task manager", long_about = None, arg_required_else_help = true)]
 pub struct Args {
     /// Sets a custom config file
     #[arg(short, long, value_name = "FILE")]
     pub config: Option<PathBuf>,
 
+    /// How lists are written, colours are left out of every format when NO_COLOR is set
+    #[arg(short, long, global = true, value_enum, default_value_t)]
+    pub output: output::Format,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
@@ -235,22 +655,195 @@ pub enum Commands {
         /// Ideal starting time for the task in this format "%Y-%m-%d" or "2022-10-24" [OPTIONAL]
         #[arg(short = 'S', long)]
         best_starting_time: Option<String>,
+        /// Where this task is created, instead of the location in the configuration [OPTIONAL]
+        #[arg(short = 'l', long)]
+        location: Option<String>,
+        /// The task this one is a part of [OPTIONAL]
+        #[arg(long)]
+        parent: Option<i64>,
+        /// When the task comes back once it is done, like "weekly on fri", "monthly on 1" or
+        /// "every 10 days after done" [OPTIONAL]
+        #[arg(short = 'r', long)]
+        repeat: Option<Recurrence>,
+        /// How much the task matters, one of low, medium or high [OPTIONAL]
+        #[arg(long)]
+        priority: Option<Priority>,
+    },
+    /// List tasks commands, every task when no list is given
+    List {
+        /// The order the tasks are listed in
+        #[arg(short, long, global = true, value_enum, default_value_t)]
+        sort: Sort,
+        /// Only the tasks that match, like "state = todo and (project = prmait or deadline < 1w)",
+        /// the project of the current directory is then left to the query
+        #[arg(short = 'w', long = "where", global = true)]
+        condition: Option<String>,
+        /// A query saved in the configuration, along with the one given
+        #[arg(short, long, global = true)]
+        query: Option<String>,
+        #[command(subcommand)]
+        which: Option<TaskListCommand>,
+    },
+    /// Start working on the task, only one task can be worked on at a time
+    Start { id: i64 },
+    /// Stop working on the task that is being worked on
+    Stop,
+    /// The time tracked on tasks this week or this month, per task, project and area
+    #[command(group(ArgGroup::new("period").required(true)))]
+    Report {
+        /// From monday to sunday
+        #[arg(long, group = "period")]
+        week: bool,
+        #[arg(long, group = "period")]
+        month: bool,
+    },
+    /// Focus on the task for a while in the terminal, ctrl-c stops the session early
+    Focus {
+        #[arg(required_unless_present = "summary")]
+        id: Option<i64>,
+        /// How long every session lasts
+        #[arg(short, long, default_value_t = 25)]
+        minutes: u16,
+        /// How long the breaks between sessions last
+        #[arg(short, long = "break", default_value_t = 5)]
+        break_minutes: u16,
+        /// How many sessions there are, with a break after every one but the last
+        #[arg(short, long, default_value_t = 1)]
+        cycles: u16,
+        /// What was focused on today, instead of focusing
+        #[arg(short, long, conflicts_with = "id")]
+        summary: bool,
+    },
+    /// The most urgent tasks to do, the ones that are blocked are left out
+    Next {
+        /// How many tasks are shown
+        #[arg(short = 'n', long, default_value_t = 5)]
+        count: usize,
     },
-    /// List tasks commands
-    #[command(subcommand)]
-    List(TaskListCommand),
     /// Set the task as done
-    Done { id: Vec<i64> },
+    Done {
+        id: Vec<i64>,
+        /// Also set the parts of the task that are still open as done
+        #[arg(short, long)]
+        recursive: bool,
+    },
     /// Set the task as backlogged
     Backlog { id: Vec<i64> },
     /// Set the task as abandoned
     Abandon {
         id: Vec<i64>,
+        /// Also abandon the parts of the task that are still open
+        #[arg(short, long)]
+        recursive: bool,
         #[arg(last = true)]
         content: Option<String>,
     },
     /// Set the task as todo
     Todo { id: Vec<i64> },
+    /// Change the state and fields of many tasks at once in the editor, one line per task, lines
+    /// without a number add new tasks
+    Bulk {
+        /// A part of the title, or a whole project, person or area [OPTIONAL]
+        filter: Option<String>,
+        /// Every task, not only the ones to do or backlogged
+        #[arg(short, long)]
+        all: bool,
+    },
+    /// Change the fields of a task, in the editor when no field is given
+    Edit {
+        id: i64,
+        /// The new title
+        #[arg(short = 't', long)]
+        title: Option<String>,
+        /// The new details, an empty one removes them
+        #[arg(short = 'd', long)]
+        description: Option<String>,
+        #[arg(short = 'a', long)]
+        area: Option<Area>,
+        /// The new deadline, in the same formats as for a new task, an empty one removes it
+        #[arg(short = 'D', long)]
+        deadline: Option<String>,
+        /// The new ideal starting time, an empty one removes it
+        #[arg(short = 'S', long)]
+        start: Option<String>,
+        #[arg(long)]
+        add_project: Vec<String>,
+        #[arg(long)]
+        remove_project: Vec<String>,
+        #[arg(long)]
+        add_person: Vec<String>,
+        #[arg(long)]
+        remove_person: Vec<String>,
+        /// The new recurrence rule, an empty one stops the task from coming back
+        #[arg(short = 'r', long)]
+        repeat: Option<String>,
+        /// The new priority, an empty one removes it
+        #[arg(long)]
+        priority: Option<String>,
+        /// Edit every field of the task as a sheet in the editor
+        #[arg(
+            short,
+            long,
+            conflicts_with_all = [
+                "title", "description", "area", "deadline", "start",
+                "add_project", "remove_project", "add_person", "remove_person", "repeat",
+                "priority",
+            ]
+        )]
+        editor: bool,
+    },
+    /// Tasks that have to be done before another one
+    #[command(subcommand)]
+    Dep(DepCommands),
+    /// Show the open tasks that depend on others, with what they depend on under them
+    Graph {
+        /// Write the graph in the DOT language instead
+        #[arg(long)]
+        dot: bool,
+    },
+    /// Show the task, and the journal entries that mention it
+    Show { id: i64 },
+    /// Maintenance of the task store itself
+    #[command(subcommand)]
+    Doctor(DoctorCommands),
+    /// Candidates for the completion scripts, one per line with its description after a tab
+    #[command(name = completion::COMPLETE_COMMAND, hide = true)]
+    Complete {
+        #[arg(value_enum)]
+        kind: CompleteKind,
+    },
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Subcommand)]
+pub enum DepCommands {
+    /// Make the task wait for the other tasks
+    Add {
+        id: i64,
+        /// The tasks that have to be done first
+        #[arg(required = true)]
+        on: Vec<i64>,
+    },
+    /// Stop the task from waiting for the other tasks
+    Rm {
+        id: i64,
+        #[arg(required = true)]
+        on: Vec<i64>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ValueEnum)]
+pub enum CompleteKind {
+    OpenTasks,
+    Projects,
+    People,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Subcommand)]
+pub enum DoctorCommands {
+    /// Rewrite every task written with an older schema to the current one
+    Migrate,
 }
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Subcommand)]
@@ -274,6 +867,18 @@ pub struct Configs {
     pub time_offset: Option<(i8, i8, i8)>,
     pub path: Option<PathBuf>,
     pub file_name_format: Option<String>,
+    /// The journal to look for entries that link to tasks in
+    pub journal_path: Option<PathBuf>,
+    /// More journals to look for entries that link to tasks in
+    pub journal_books: Option<Vec<PathBuf>>,
+    /// What is recorded about where new tasks are created, everything if not set
+    pub context: Option<context::Config>,
+    /// The weights of the projects in the urgency of their tasks
+    pub urgency: Option<tasks::urgency::Config>,
+    /// How the end of focus sessions is let known, the terminal bell if not set
+    pub focus: Option<tasks::focus::Config>,
+    /// Queries for `tsk list --query`, by their names
+    pub queries: Option<BTreeMap<String, String>>,
 }
 
 impl Configs {
@@ -0,0 +1,328 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use clap_complete_command::Shell;
+
+use crate::effects::{EffectKind, EffectMachine};
+use crate::journal::Book;
+use crate::tasks::task::State;
+use crate::tasks::tasklist::TaskList;
+
+/// The name of the hidden subcommand that the completion scripts ask for candidates.
+pub const COMPLETE_COMMAND: &str = "_complete";
+
+/// A value the shell can complete to, printed as `value<TAB>description`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Candidate {
+    pub value: String,
+    pub description: Option<String>,
+}
+
+impl Display for Candidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.description {
+            Some(description) => write!(f, "{}\t{}", self.value, description.replace('\n', " ")),
+            None => write!(f, "{}", self.value),
+        }
+    }
+}
+
+/// Where in the command line the candidates of a kind are completed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Rule {
+    pub subcommand: &'static str,
+    /// The short and long name of the option whose value is completed, the positional arguments
+    /// of the subcommand are completed when this is not given
+    pub option: Option<(char, &'static str)>,
+    /// What is passed to the hidden subcommand
+    pub kind: &'static str,
+}
+
+/// Tasks that are still to do, newest first, described by their titles.
+#[must_use]
+pub fn open_tasks(tasks: &TaskList) -> Vec<Candidate> {
+    tasks
+        .0
+        .iter()
+        .rev()
+        .filter(|t| matches!(t.task.current_state(), Some(State::ToDo(_))))
+        .map(|t| Candidate {
+            value: t.task.id.to_string(),
+            description: Some(t.task.title.clone()),
+        })
+        .collect()
+}
+
+/// Every project that a task belongs to.
+#[must_use]
+pub fn task_projects(tasks: &TaskList) -> Vec<Candidate> {
+    counted(tasks.0.iter().flat_map(|t| &t.task.projects))
+}
+
+/// Everyone that a task is related to.
+#[must_use]
+pub fn task_people(tasks: &TaskList) -> Vec<Candidate> {
+    counted(tasks.0.iter().flat_map(|t| &t.task.people))
+}
+
+/// Every tag used in the journal.
+#[must_use]
+pub fn entry_tags(book: &Book) -> Vec<Candidate> {
+    counted(book.entries.iter().flat_map(|e| &e.entry.tag))
+}
+
+/// Everyone mentioned in the journal.
+#[must_use]
+pub fn entry_people(book: &Book) -> Vec<Candidate> {
+    counted(book.entries.iter().flat_map(|e| &e.entry.people))
+}
+
+/// The distinct values, most used first, described by how often they are used.
+fn counted<'a>(values: impl Iterator<Item = &'a String>) -> Vec<Candidate> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for value in values {
+        *counts.entry(value.as_str()).or_default() += 1;
+    }
+    let mut counts: Vec<(&str, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    counts
+        .into_iter()
+        .map(|(value, count)| Candidate {
+            value: value.to_owned(),
+            description: Some(if count == 1 {
+                "used once".to_owned()
+            } else {
+                format!("used {count} times")
+            }),
+        })
+        .collect()
+}
+
+/// Prints the candidates, one per line.
+#[must_use]
+pub fn candidates(candidates: &[Candidate]) -> EffectMachine {
+    let mut efs = EffectMachine::default();
+    if !candidates.is_empty() {
+        efs.add(
+            EffectKind::PrintToStdOut(
+                candidates
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            false,
+        );
+    }
+    efs
+}
+
+/// The static completion script of the command, followed by the script that completes the values
+/// of the rules by asking the binary, for the shells that can.
+#[must_use]
+pub fn generate(shell: Shell, command: clap::Command, rules: &[Rule]) -> EffectMachine {
+    let script = dynamic_script(shell, command.get_name(), rules);
+    let mut efs = EffectMachine::default();
+    efs.add(EffectKind::GenerateShellCompletion(shell, command), false);
+    if let Some(script) = script {
+        efs.add(EffectKind::PrintToStdOut(script), false);
+    }
+    efs
+}
+
+/// Options given before the subcommand, like the config file, are passed on to the hidden
+/// subcommand, every one of them is expected to take a value.
+#[must_use]
+pub fn dynamic_script(shell: Shell, bin: &str, rules: &[Rule]) -> Option<String> {
+    let function = format!("_{}", bin.replace('-', "_"));
+    match shell {
+        Shell::Bash => Some(bash(bin, &function, rules)),
+        Shell::Zsh => Some(zsh(bin, &function, rules)),
+        Shell::Fish => Some(fish(bin, rules)),
+        _ => None,
+    }
+}
+
+fn case_patterns(rule: &Rule) -> String {
+    match rule.option {
+        Some((short, long)) => format!("{0},-{short}|{0},--{long}", rule.subcommand),
+        None => format!("{},[!-]*", rule.subcommand),
+    }
+}
+
+fn bash(bin: &str, function: &str, rules: &[Rule]) -> String {
+    let cases: String = rules
+        .iter()
+        .map(|r| format!("        {}) kind=\"{}\" ;;\n", case_patterns(r), r.kind))
+        .collect();
+    format!(
+        r#"
+{function}_dynamic() {{
+    local i=1 sub="" kind="" cur="${{COMP_WORDS[COMP_CWORD]}}" prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    while [[ $i -lt $COMP_CWORD ]]; do
+        case "${{COMP_WORDS[i]}}" in
+            -*=*) i=$((i + 1)) ;;
+            -*) i=$((i + 2)) ;;
+            *) sub="${{COMP_WORDS[i]}}"; break ;;
+        esac
+    done
+    case "$sub,$prev" in
+{cases}    esac
+    if [[ -z "$sub" || -z "$kind" || "$cur" == -* ]]; then
+        {function} "$@"
+        return
+    fi
+    local IFS=$'\n'
+    COMPREPLY=($(compgen -W "$({bin} "${{COMP_WORDS[@]:1:i-1}}" {COMPLETE_COMMAND} "$kind" 2>/dev/null | cut -f1)" -- "$cur"))
+}}
+complete -F {function}_dynamic -o nosort -o bashdefault -o default {bin}
+"#
+    )
+}
+
+fn zsh(bin: &str, function: &str, rules: &[Rule]) -> String {
+    let cases: String = rules
+        .iter()
+        .map(|r| {
+            let pattern = match r.option {
+                Some((short, long)) => format!("{0},-{short}|{0},--{long}", r.subcommand),
+                None => format!("{},[^-]*", r.subcommand),
+            };
+            format!("        ({pattern}) kind=\"{}\" ;;\n", r.kind)
+        })
+        .collect();
+    format!(
+        r#"
+{function}_dynamic() {{
+    local i=2 sub="" kind="" prev="${{words[CURRENT-1]}}"
+    while (( i < CURRENT )); do
+        case "${{words[i]}}" in
+            (-*=*) (( i += 1 )) ;;
+            (-*) (( i += 2 )) ;;
+            (*) sub="${{words[i]}}"; break ;;
+        esac
+    done
+    case "$sub,$prev" in
+{cases}    esac
+    if [[ -z "$sub" || -z "$kind" || "${{words[CURRENT]}}" == -* ]]; then
+        {function} "$@"
+        return
+    fi
+    local -a candidates
+    candidates=("${{(@f)$({bin} "${{(@)words[2,i-1]}}" {COMPLETE_COMMAND} "$kind" 2>/dev/null | sed -e 's/:/\\:/g' -e 's/\t/:/')}}")
+    _describe -t "$kind" "$kind" candidates
+}}
+compdef {function}_dynamic {bin}
+"#
+    )
+}
+
+fn fish(bin: &str, rules: &[Rule]) -> String {
+    let completes: String = rules
+        .iter()
+        .map(|r| {
+            let option = r
+                .option
+                .map(|(short, long)| format!(" -s {short} -l {long} -r"))
+                .unwrap_or_default();
+            format!(
+                "complete -c {bin} -n \"__fish_seen_subcommand_from {}\"{option} -f -k -a \"(__{bin}_complete {})\"\n",
+                r.subcommand, r.kind
+            )
+        })
+        .collect();
+    format!(
+        r#"
+function __{bin}_complete
+    set -l words (commandline -opc)
+    set -l globals
+    set -l i 2
+    while test $i -le (count $words)
+        switch $words[$i]
+            case '-*=*'
+                set -a globals $words[$i]
+                set i (math $i + 1)
+            case '-*'
+                set -a globals $words[$i] $words[(math $i + 1)]
+                set i (math $i + 2)
+            case '*'
+                break
+        end
+    end
+    {bin} $globals {COMPLETE_COMMAND} $argv 2>/dev/null
+end
+{completes}"#
+    )
+}
+
+#[cfg(test)]
+mod testing {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::journal::{migration, Entry, EntryDescription, Mood};
+    use rstest::*;
+
+    const fn is_normal<T: Sized + Send + Sync + Unpin>() {}
+
+    #[test]
+    const fn normal_types() {
+        is_normal::<Candidate>();
+        is_normal::<Rule>();
+    }
+
+    fn entry_description(tag: &[&str]) -> EntryDescription {
+        EntryDescription {
+            entry: Entry {
+                schema_version: migration::CURRENT_VERSION,
+                at: time::OffsetDateTime::UNIX_EPOCH,
+                body: Arc::new(String::new()),
+                tag: tag.iter().map(|&t| t.to_owned()).collect(),
+                mood: Mood::default(),
+                people: vec![],
+                attachments: vec![],
+                context: None,
+            },
+            file_name: String::new(),
+        }
+    }
+
+    #[rstest]
+    fn tags_most_used_first() {
+        let book = Book::from((
+            vec![
+                entry_description(&["work", "rust"]),
+                entry_description(&["rust"]),
+            ],
+            PathBuf::new(),
+        ));
+        let tags: Vec<String> = entry_tags(&book).iter().map(ToString::to_string).collect();
+        assert_eq!(tags, ["rust\tused 2 times", "work\tused once"]);
+    }
+
+    #[rstest]
+    #[case::bash(Shell::Bash, "done,[!-]*) kind=\"open-tasks\"")]
+    #[case::zsh(Shell::Zsh, "(new,-p|new,--projects) kind=\"projects\"")]
+    #[case::fish(
+        Shell::Fish,
+        "-s p -l projects -r -f -k -a \"(__tsk_complete projects)\""
+    )]
+    fn scripts(#[case] shell: Shell, #[case] expected: &str) {
+        let rules = [
+            Rule {
+                subcommand: "done",
+                option: None,
+                kind: "open-tasks",
+            },
+            Rule {
+                subcommand: "new",
+                option: Some(('p', "projects")),
+                kind: "projects",
+            },
+        ];
+        let script = dynamic_script(shell, "tsk", &rules).unwrap_or_default();
+        assert!(script.contains(expected), "{script}");
+        assert!(script.contains("tsk"));
+    }
+}
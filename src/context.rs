@@ -0,0 +1,139 @@
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use crate::git;
+
+/// Where an entry or a task was written.
+///
+/// Nothing is skipped when serializing, the index caches these with bincode, which can not tell a
+/// skipped field from a missing one.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Context {
+    #[serde(default)]
+    pub hostname: Option<String>,
+    /// The name of the git repository it was written in
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    /// Given by hand, or set in the configuration
+    #[serde(default)]
+    pub location: Option<String>,
+}
+
+impl Display for Context {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts: Vec<String> = [
+            self.location.as_ref().map(|l| format!("at {l}")),
+            self.project.as_ref().map(|p| format!("on {p}")),
+            self.hostname.as_ref().map(|h| format!("from {h}")),
+            self.cwd.as_ref().map(|c| format!("in {}", c.display())),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// Which fields of the context are captured, every one of them is unless it is turned off.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Config {
+    pub hostname: Option<bool>,
+    pub project: Option<bool>,
+    pub cwd: Option<bool>,
+    /// Used when no location is given by hand
+    pub location: Option<String>,
+}
+
+impl Config {
+    /// The context of work done in the directory, `None` when nothing could be captured.
+    #[must_use]
+    pub fn capture(&self, cwd: &Path, location: Option<String>) -> Option<Context> {
+        let context = Context {
+            hostname: self.hostname.unwrap_or(true).then(hostname).flatten(),
+            project: self
+                .project
+                .unwrap_or(true)
+                .then(|| git::repo_directory_name(cwd).ok())
+                .flatten(),
+            cwd: self.cwd.unwrap_or(true).then(|| cwd.to_path_buf()),
+            location: location.or_else(|| self.location.clone()),
+        };
+        (context != Context::default()).then_some(context)
+    }
+}
+
+fn hostname() -> Option<String> {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/proc/sys/kernel/hostname").ok())
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().to_owned())
+        .filter(|h| !h.is_empty())
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use rstest::*;
+
+    const fn is_normal<T: Sized + Send + Sync + Unpin>() {}
+
+    #[test]
+    const fn normal_types() {
+        is_normal::<Context>();
+        is_normal::<Config>();
+    }
+
+    #[rstest]
+    fn nothing_captured() {
+        let config = Config {
+            hostname: Some(false),
+            project: Some(false),
+            cwd: Some(false),
+            location: None,
+        };
+        assert_eq!(config.capture(Path::new("/"), None), None);
+        assert_eq!(
+            config
+                .capture(Path::new("/"), Some("home".to_owned()))
+                .map(|c| c.to_string()),
+            Some("at home".to_owned())
+        );
+    }
+
+    #[rstest]
+    fn given_location_wins() {
+        let config = Config {
+            hostname: Some(false),
+            project: Some(false),
+            cwd: None,
+            location: Some("office".to_owned()),
+        };
+        assert_eq!(
+            config.capture(Path::new("/tmp"), Some("train".to_owned())),
+            Some(Context {
+                cwd: Some(PathBuf::from("/tmp")),
+                location: Some("train".to_owned()),
+                ..Context::default()
+            })
+        );
+    }
+
+    #[rstest]
+    fn reads_back() {
+        let context: Option<Context> = serde_json::from_str("{}").ok();
+        assert_eq!(context, Some(Context::default()));
+        let context = Context {
+            project: Some("prmait".to_owned()),
+            ..Context::default()
+        };
+        let cached: Option<Context> = bincode::serialize(&context)
+            .ok()
+            .and_then(|c| bincode::deserialize(&c).ok());
+        assert_eq!(cached, Some(context));
+    }
+}
@@ -1,14 +1,16 @@
-use std::{collections, path::PathBuf};
+use std::{collections, io::Write, path::PathBuf};
 
 use clap_complete_command::Shell;
 use tracing::{debug, error, info, trace};
 
-use crate::git;
+use crate::{git, output};
 
 #[derive(Clone, Debug)]
 pub enum EffectKind {
     WriteToFile(FileWriterOpts),
+    WriteToDraft(DraftWriterOpts),
     CreateDir(CreateDirOpts),
+    RemoveFile(RemoveFileOpts),
     OpenInEditor(OpenInEditorOpts),
     PrintToStdOut(String),
     PrintToStdErr(String),
@@ -21,10 +23,12 @@ impl EffectKind {
     pub fn apply(self) -> Result<()> {
         match self {
             Self::WriteToFile(opts) => file_writer(opts),
+            Self::WriteToDraft(opts) => draft_writer(opts),
             Self::CreateDir(opts) => dir_creator(opts),
+            Self::RemoveFile(opts) => file_remover(opts),
             Self::OpenInEditor(opts) => editor_opener(opts),
-            Self::PrintToStdOut(text) => Ok(println!("{text}")), // I know :D!
-            Self::PrintToStdErr(text) => Ok(eprintln!("{text}")), // I know :D!
+            Self::PrintToStdOut(text) => print_line(std::io::stdout().lock(), &text),
+            Self::PrintToStdErr(text) => print_line(std::io::stderr().lock(), &text),
             Self::GenerateShellCompletion(shell, mut cmd) => {
                 shell.generate(&mut cmd, &mut std::io::stdout());
                 Ok(())
@@ -47,6 +51,20 @@ impl EffectKind {
         }
     }
 }
+/// Colours are left out when `NO_COLOR` is set, and a reader that stopped reading, like `head`,
+/// is not an error.
+fn print_line(mut out: impl Write, text: &str) -> Result<()> {
+    let written = if output::no_color() {
+        writeln!(out, "{}", output::strip_ansi(text))
+    } else {
+        writeln!(out, "{text}")
+    };
+    match written {
+        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => Err(Error::CouldNotPrint(e)),
+        _ => Ok(()),
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Effect {
     pub effect_kind: EffectKind,
@@ -144,6 +162,10 @@ pub enum Error {
     TaskReturnedError(std::io::Error),
     #[error("repo dir includes non-standard characters")]
     UnstandardPath,
+    #[error("could not remove the file: {0}")]
+    CouldNotRemoveFile(std::io::Error),
+    #[error("could not print: {0}")]
+    CouldNotPrint(std::io::Error),
 }
 
 type Result<T> = std::result::Result<T, Error>;
@@ -203,6 +225,30 @@ fn file_writer(opts: FileWriterOpts) -> Result<()> {
     std::fs::write(opts.file_path, opts.content).map_err(Error::CouldNotWriteToFile)
 }
 
+/// A file that may hold secrets or unsaved work, see [`crate::files::DraftDir`].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DraftWriterOpts {
+    pub content: Vec<u8>,
+    pub file_path: PathBuf,
+}
+
+/// Only the user can read the draft, and it is never written through something that is already
+/// there, like a link planted in its place.
+#[tracing::instrument(skip(opts), fields(file_path = ?opts.file_path))]
+fn draft_writer(opts: DraftWriterOpts) -> Result<()> {
+    trace!(stage = "starting to write the draft");
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(&opts.file_path)
+        .map_err(Error::CouldNotWriteToFile)?;
+    file.write_all(&opts.content)
+        .map_err(Error::CouldNotWriteToFile)
+}
+
 #[tracing::instrument]
 fn dir_creator(opts: CreateDirOpts) -> Result<()> {
     trace!(stage = "dir_creator is starting");
@@ -231,6 +277,50 @@ pub struct CreateDirOpts {
     pub ok_if_exists: bool,
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RemoveFileOpts {
+    pub file_path: PathBuf,
+    pub ok_if_missing: bool,
+    /// Overwrite the content with zeros before removing, for files that held secrets
+    pub shred: bool,
+}
+
+#[tracing::instrument]
+fn file_remover(opts: RemoveFileOpts) -> Result<()> {
+    trace!(stage = "starting to remove the file");
+
+    let exists = opts
+        .file_path
+        .try_exists()
+        .map_err(Error::CheckFileExistenceFailed)?;
+    if !exists {
+        if opts.ok_if_missing {
+            debug!(stage = "not removing the file, it does not exist");
+            return Ok(());
+        }
+        error!(stage = "file to remove does not exist");
+        return Err(Error::FileDoesNotExists);
+    }
+
+    if opts.shred {
+        info!(stage = "shredding file");
+        let len = std::fs::metadata(&opts.file_path)
+            .map_err(Error::CouldNotRemoveFile)?
+            .len();
+        let zeros = vec![0_u8; usize::try_from(len).unwrap_or_default()];
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&opts.file_path)
+            .map_err(Error::CouldNotRemoveFile)?;
+        file.write_all(&zeros).map_err(Error::CouldNotRemoveFile)?;
+        file.sync_all().map_err(Error::CouldNotRemoveFile)?;
+    }
+
+    info!(stage = "removing file");
+    std::fs::remove_file(opts.file_path).map_err(Error::CouldNotRemoveFile)
+}
+
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct GitHookOpts {
@@ -241,6 +331,7 @@ pub struct GitHookOpts {
 
 #[cfg(test)]
 mod testing {
+    #![allow(clippy::unwrap_used)]
     #[allow(clippy::wildcard_imports)]
     use super::*;
     const fn is_normal<T: Sized + Send + Sync + Unpin>() {}
@@ -248,4 +339,29 @@ mod testing {
     const fn normal_types() {
         is_normal::<EffectKind>();
     }
+
+    #[test]
+    fn drafts_are_private_and_new() {
+        let dir = crate::files::DraftDir::new("prmait-test-").unwrap();
+        let file_path = dir.path().join("draft");
+        let write = || {
+            EffectKind::WriteToDraft(DraftWriterOpts {
+                content: b"secret".to_vec(),
+                file_path: file_path.clone(),
+            })
+            .apply()
+        };
+        write().unwrap();
+        assert!(matches!(write(), Err(Error::CouldNotWriteToFile(_))));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |p: &std::path::Path| std::fs::metadata(p).unwrap().permissions().mode();
+            assert_eq!(mode(&file_path) & 0o777, 0o600);
+            assert_eq!(mode(dir.path()) & 0o777, 0o700);
+        }
+        let dir_path = dir.path().to_path_buf();
+        drop(dir);
+        assert!(!dir_path.exists());
+    }
 }
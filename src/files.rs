@@ -1,6 +1,9 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use time::formatting::Formattable;
+use tracing::warn;
+
+use crate::effects::{EffectKind, RemoveFileOpts};
 
 #[allow(clippy::ptr_arg)] // the whole function is just to here for making it easier to read
 #[must_use]
@@ -11,6 +14,69 @@ pub fn is_json(p: &PathBuf) -> bool {
     }
 }
 
+/// Every json file under the directory and its subdirectories.
+///
+/// Unlike `fs_extra::dir::get_dir_content` it does not stat every file, which adds up for stores
+/// with tens of thousands of them.
+pub fn list_json(dir: &Path) -> Result<Vec<PathBuf>, fs_extra::error::Error> {
+    let mut files = vec![];
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                dirs.push(path);
+            } else if is_json(&path) {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// A directory of its own under the temporary directory that only the user can get into, for
+/// drafts that hold decrypted entries or work that is not saved yet. Whatever is left in it is
+/// shredded and the directory removed when it is dropped, however the command ended.
+#[derive(Debug)]
+pub struct DraftDir(tempfile::TempDir);
+
+impl DraftDir {
+    pub fn new(prefix: &str) -> std::io::Result<Self> {
+        let dir = tempfile::Builder::new().prefix(prefix).tempdir()?;
+        #[cfg(unix)]
+        std::fs::set_permissions(
+            dir.path(),
+            std::os::unix::fs::PermissionsExt::from_mode(0o700),
+        )?;
+        Ok(Self(dir))
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        self.0.path()
+    }
+}
+
+impl Drop for DraftDir {
+    fn drop(&mut self) {
+        let Ok(entries) = std::fs::read_dir(self.path()) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let shredded = EffectKind::RemoveFile(RemoveFileOpts {
+                file_path: entry.path(),
+                ok_if_missing: true,
+                shred: true,
+            })
+            .apply();
+            if let Err(e) = shredded {
+                warn!("could not shred the draft {}: {e}", entry.path().display());
+            }
+        }
+    }
+}
+
 pub trait ToFileName {
     type Error;
     fn to_file_name(
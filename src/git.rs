@@ -81,6 +81,55 @@ pub fn full_hook(repo_root: &str, files: &[String], commit_message: &str) -> Eff
     efm
 }
 
+/// How much of git a store goes through after it is changed.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Behaviour {
+    /// Add, commit, pull and push
+    #[default]
+    Sync,
+    /// Add and commit, for stores without a remote
+    Commit,
+    /// Leave git alone, for stores that are not in a repository
+    Off,
+}
+
+impl Behaviour {
+    /// The same effects, without the git commands this behaviour leaves out.
+    #[must_use]
+    pub fn restrict(self, machine: EffectMachine) -> EffectMachine {
+        EffectMachine(
+            machine
+                .0
+                .into_iter()
+                .filter_map(|mut effect| {
+                    match &mut effect.effect_kind {
+                        EffectKind::RunExternalCommand(cmd, args, _) if cmd == "git" => {
+                            let allowed = match self {
+                                Self::Sync => true,
+                                Self::Commit => !matches!(
+                                    args.get(2).map(String::as_str),
+                                    Some("pull" | "push")
+                                ),
+                                Self::Off => false,
+                            };
+                            if !allowed {
+                                return None;
+                            }
+                        }
+                        EffectKind::RunAsyncMachine(inner) => {
+                            *inner = self.restrict(std::mem::take(inner));
+                        }
+                        _ => {}
+                    }
+                    Some(effect)
+                })
+                .collect(),
+        )
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("could not get current working directory {0}")]
@@ -98,3 +147,34 @@ pub enum Error {
     #[error("command returned non-zero status: {0}")]
     CommandReturnedNon0StatusCode(i32),
 }
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use rstest::*;
+
+    fn commands(machine: &EffectMachine) -> Vec<String> {
+        machine
+            .0
+            .iter()
+            .filter_map(|e| match &e.effect_kind {
+                EffectKind::RunExternalCommand(_, args, _) => args.get(2).cloned(),
+                EffectKind::PrintToStdOut(text) => Some(text.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case::sync(Behaviour::Sync, vec!["done", "add", "commit", "pull", "push"])]
+    #[case::commit(Behaviour::Commit, vec!["done", "add", "commit"])]
+    #[case::off(Behaviour::Off, vec!["done"])]
+    fn restrict(#[case] behaviour: Behaviour, #[case] expected: Vec<&str>) {
+        let mut machine = EffectMachine::default();
+        machine.add(EffectKind::PrintToStdOut("done".to_owned()), false);
+        machine
+            .0
+            .extend(full_hook("/repo", &["a".to_owned()], "message").0);
+        assert_eq!(commands(&behaviour.restrict(machine)), expected);
+    }
+}
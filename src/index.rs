@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tracing::{debug, trace};
+
+/// The cache file kept at the root of a store, it is ignored by git through the `.gitignore` next
+/// to it.
+pub const INDEX_FILE_NAME: &str = ".prmait-index";
+
+/// Bumped when the layout of the index itself changes, the index is bincode so anything that
+/// changes the order or the types of the fields counts, like the `context` that entries and tasks
+/// gained in version 2.
+const INDEX_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize)]
+struct Index<F, T> {
+    /// The index version and the schema version of the cached documents
+    version: (u32, u32),
+    /// Paths are relative to the store, so the store can be moved around
+    files: Vec<(F, Cached<T>)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Cached<T> {
+    modified: SystemTime,
+    size: u64,
+    item: T,
+}
+
+/// Parses the files of the store, reusing what the index of the store has for every file whose
+/// modification time and size have not changed since it was parsed.
+///
+/// The index is rebuilt when it is missing, can not be read, or was written for another
+/// `schema_version`. Failing to write the index back is not an error, the next run just parses
+/// again.
+pub fn load<T, E>(
+    dir: &Path,
+    files: Vec<PathBuf>,
+    schema_version: u32,
+    parse: impl Fn(&Path) -> Result<T, E>,
+) -> Result<Vec<T>, E>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let index_path = dir.join(INDEX_FILE_NAME);
+    let version = (INDEX_VERSION, schema_version);
+    let mut old: HashMap<String, Cached<T>> = std::fs::read(&index_path)
+        .ok()
+        .and_then(|content| bincode::deserialize::<Index<_, _>>(&content).ok())
+        .filter(|index| index.version == version)
+        .map(|index| index.files.into_iter().collect())
+        .unwrap_or_default();
+
+    let mut changed = old.len() != files.len();
+    let mut loaded = Vec::with_capacity(files.len());
+    for file in files {
+        let key = file
+            .strip_prefix(dir)
+            .unwrap_or(&file)
+            .to_string_lossy()
+            .into_owned();
+        let stat = std::fs::metadata(&file)
+            .ok()
+            .and_then(|m| Some((m.modified().ok()?, m.len())));
+        let cached = old.remove(&key).filter(|c| {
+            stat.is_some_and(|(modified, size)| c.modified == modified && c.size == size)
+        });
+        let item = match cached {
+            Some(cached) => cached.item,
+            None => {
+                trace!(file = ?file, "not in the index");
+                changed = true;
+                parse(&file)?
+            }
+        };
+        loaded.push((key, stat, item));
+    }
+
+    if changed {
+        debug!(index = ?index_path, "writing the index");
+        let index = Index {
+            version,
+            files: loaded
+                .iter()
+                .filter_map(|(key, stat, item)| {
+                    let (modified, size) = (*stat)?;
+                    Some((
+                        key,
+                        Cached {
+                            modified,
+                            size,
+                            item,
+                        },
+                    ))
+                })
+                .collect(),
+        };
+        if let Ok(content) = bincode::serialize(&index) {
+            if std::fs::write(&index_path, content).is_ok() {
+                ignore_in_git(dir);
+            }
+        }
+    }
+
+    Ok(loaded.into_iter().map(|(_, _, item)| item).collect())
+}
+
+/// Makes sure the `.gitignore` of the store leaves the index out.
+fn ignore_in_git(dir: &Path) {
+    let gitignore = dir.join(".gitignore");
+    let content = std::fs::read_to_string(&gitignore).unwrap_or_default();
+    if content.lines().any(|l| l.trim() == INDEX_FILE_NAME) {
+        return;
+    }
+    let separator = if content.is_empty() || content.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+    let _ignored = std::fs::write(
+        gitignore,
+        format!("{content}{separator}{INDEX_FILE_NAME}\n"),
+    );
+}
+
+#[cfg(test)]
+mod testing {
+    #![allow(clippy::unwrap_used)]
+
+    use std::cell::Cell;
+
+    use super::*;
+    use rstest::*;
+
+    fn store(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("prmait-index-{name}-{}", std::process::id()));
+        let _ignored = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn read(path: &Path, parsed: &Cell<usize>) -> Result<String, std::io::Error> {
+        parsed.set(parsed.get() + 1);
+        std::fs::read_to_string(path)
+    }
+
+    #[rstest]
+    fn only_changed_files_are_parsed() {
+        let dir = store("changed");
+        let (a, b) = (dir.join("a.json"), dir.join("b.json"));
+        std::fs::write(&a, "a").unwrap();
+        std::fs::write(&b, "b").unwrap();
+        let parsed = Cell::new(0);
+
+        let items = load(&dir, vec![a.clone(), b.clone()], 1, |p| read(p, &parsed)).unwrap();
+        assert_eq!(items, ["a", "b"]);
+        assert_eq!(parsed.get(), 2);
+
+        let items = load(&dir, vec![a.clone(), b.clone()], 1, |p| read(p, &parsed)).unwrap();
+        assert_eq!(items, ["a", "b"]);
+        assert_eq!(parsed.get(), 2);
+
+        std::fs::write(&b, "bb").unwrap();
+        let items = load(&dir, vec![a.clone(), b.clone()], 1, |p| read(p, &parsed)).unwrap();
+        assert_eq!(items, ["a", "bb"]);
+        assert_eq!(parsed.get(), 3);
+
+        // a newer schema invalidates everything
+        load(&dir, vec![a, b], 2, |p| read(p, &parsed)).unwrap();
+        assert_eq!(parsed.get(), 5);
+
+        assert_eq!(
+            std::fs::read_to_string(dir.join(".gitignore")).unwrap(),
+            ".prmait-index\n"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[rstest]
+    fn corrupt_index_is_rebuilt() {
+        let dir = store("corrupt");
+        let a = dir.join("a.json");
+        std::fs::write(&a, "a").unwrap();
+        std::fs::write(dir.join(INDEX_FILE_NAME), "not json").unwrap();
+        let parsed = Cell::new(0);
+
+        let items = load(&dir, vec![a.clone()], 1, |p| read(p, &parsed)).unwrap();
+        assert_eq!(items, ["a"]);
+        load(&dir, vec![a], 1, |p| read(p, &parsed)).unwrap();
+        assert_eq!(parsed.get(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,7 +1,15 @@
+pub mod attachments;
+pub use attachments::*;
 pub mod book;
 pub use book::*;
 pub mod entry;
 pub use entry::*;
 pub mod effectors;
+pub mod encryption;
+pub use encryption::Keyring;
+pub mod frontmatter;
+pub use frontmatter::*;
+pub mod import;
+pub mod migration;
 pub use error::*;
 pub mod error;
@@ -0,0 +1,165 @@
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use super::{Error, Keyring};
+
+/// The directory inside the journal where attached files are kept, named after their hash.
+pub const ATTACHMENTS_DIR: &str = "attachments";
+/// The extension of attachments that were sealed, in place of their own.
+pub const SEALED_EXTENSION: &str = "sealed";
+
+/// A file attached to an entry, the same file attached twice is stored only once.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Attachment {
+    /// Hex encoded sha256 of the content
+    pub hash: String,
+    /// The name of the file when it was attached
+    pub name: String,
+}
+
+impl Attachment {
+    /// The name of the stored file, the hash keeps the extension so it still opens with the right
+    /// program. Sealed files hold an envelope rather than what the extension says, so they are
+    /// marked as sealed instead.
+    #[must_use]
+    pub fn stored_name(&self, sealed: bool) -> String {
+        if sealed {
+            return format!("{}.{SEALED_EXTENSION}", self.hash);
+        }
+        match Path::new(&self.name).extension() {
+            Some(extension) => format!("{}.{}", self.hash, extension.to_string_lossy()),
+            None => self.hash.clone(),
+        }
+    }
+
+    #[must_use]
+    pub fn stored_path(&self, journal_path: &Path, sealed: bool) -> PathBuf {
+        journal_path
+            .join(ATTACHMENTS_DIR)
+            .join(self.stored_name(sealed))
+    }
+
+    /// The content of the attachment, decrypted if it was sealed. A sealed copy is preferred when
+    /// there is a keyring, and a plain one is used otherwise.
+    pub fn read(&self, journal_path: &Path, keyring: Option<&Keyring>) -> Result<Vec<u8>, Error> {
+        let sealed = self.stored_path(journal_path, true);
+        let file_path = if keyring.is_some() && sealed.exists() {
+            sealed
+        } else {
+            self.stored_path(journal_path, false)
+        };
+        let content = std::fs::read(file_path).map_err(Error::AttachmentCouldNotBeRead)?;
+        match keyring {
+            Some(keyring) => keyring.open_bytes(&content),
+            None => Ok(content),
+        }
+    }
+}
+
+impl Display for Attachment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({})",
+            self.name,
+            self.hash.get(..8).unwrap_or(&self.hash)
+        )
+    }
+}
+
+/// An attachment along with its content, before it is stored.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AttachmentFile {
+    pub attachment: Attachment,
+    pub content: Vec<u8>,
+}
+
+impl AttachmentFile {
+    #[must_use]
+    pub fn new(name: String, content: Vec<u8>) -> Self {
+        let hash = Sha256::digest(&content)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        Self {
+            attachment: Attachment { hash, name },
+            content,
+        }
+    }
+}
+
+impl TryFrom<&PathBuf> for AttachmentFile {
+    type Error = Error;
+
+    fn try_from(value: &PathBuf) -> Result<Self, Self::Error> {
+        let name = value
+            .file_name()
+            .ok_or(Error::IsNotAFile)?
+            .to_str()
+            .ok_or(Error::FileNameHasInvalidCharacters)?
+            .to_owned();
+        let content = std::fs::read(value).map_err(Error::AttachmentCouldNotBeRead)?;
+        Ok(Self::new(name, content))
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use rstest::*;
+
+    const fn is_normal<T: Sized + Send + Sync + Unpin>() {}
+
+    #[test]
+    const fn normal_types() {
+        is_normal::<Attachment>();
+        is_normal::<AttachmentFile>();
+    }
+
+    #[rstest]
+    #[case::with_extension(
+        "photo.jpg",
+        "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824.jpg"
+    )]
+    #[case::without_extension(
+        "memo",
+        "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+    )]
+    fn stored_by_hash(#[case] name: &str, #[case] stored_name: &str) {
+        let file = AttachmentFile::new(name.to_owned(), b"hello".to_vec());
+        assert_eq!(file.attachment.stored_name(false), stored_name);
+        assert_eq!(
+            file.attachment.stored_path(Path::new("journal"), false),
+            PathBuf::from("journal/attachments").join(stored_name)
+        );
+        assert_eq!(
+            file.attachment.stored_name(true),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824.sealed"
+        );
+    }
+
+    #[rstest]
+    fn sealed_attachments_read_back() {
+        let journal = tempfile::tempdir().unwrap();
+        std::fs::create_dir(journal.path().join(ATTACHMENTS_DIR)).unwrap();
+        let keyring = Keyring::new(b"correct horse".to_vec());
+        let pixels = vec![0xff_u8, 0xd8, 0xff, 0xe0, 0x00, 0x80];
+        let file = AttachmentFile::new("photo.jpg".to_owned(), pixels.clone());
+        std::fs::write(
+            file.attachment.stored_path(journal.path(), true),
+            keyring.seal(&pixels).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            file.attachment
+                .read(journal.path(), Some(&keyring))
+                .unwrap(),
+            pixels
+        );
+    }
+}
@@ -1,11 +1,16 @@
-use crate::files::is_json;
+use crate::files::list_json;
 use crate::fold_or_err;
-use crate::journal::entry::Entry;
-use crate::journal::Error;
+use crate::index;
+use crate::journal::entry::{Entry, Mood};
+use crate::journal::{migration, Error, Keyring, ATTACHMENTS_DIR};
+use crate::output::Row;
 use comfy_table::{Cell, ContentArrangement};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use time::formatting::Formattable;
+use time::{Date, Duration, Month, UtcOffset};
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -25,6 +30,12 @@ impl TryFrom<PathBuf> for EntryDescription {
     type Error = Error;
 
     fn try_from(value: PathBuf) -> Result<Self, Self::Error> {
+        Self::read(&value, None)
+    }
+}
+
+impl EntryDescription {
+    pub fn read(value: &Path, keyring: Option<&Keyring>) -> Result<Self, Error> {
         let file_name = value
             .file_name()
             .ok_or(Error::IsNotAFile)?
@@ -32,7 +43,7 @@ impl TryFrom<PathBuf> for EntryDescription {
             .ok_or(Error::FileNameHasInvalidCharacters)?
             .to_owned();
         Ok(Self {
-            entry: Entry::try_from(value).map_err(|e| {
+            entry: Entry::read(value, keyring).map_err(|e| {
                 Error::CouldNotDeserializeEntryFromJson(Box::new(e), file_name.clone())
             })?,
             file_name,
@@ -40,6 +51,23 @@ impl TryFrom<PathBuf> for EntryDescription {
     }
 }
 impl Book {
+    /// The entries that can be read without a keyring, for tools that only glance at the journal,
+    /// along with the file names of the ones that could not be read, like sealed ones.
+    pub fn readable(journal_path: &PathBuf) -> Result<(Self, Vec<String>), Error> {
+        if let Ok(book) = Self::try_from(journal_path) {
+            return Ok((book, vec![]));
+        }
+        let mut entries = vec![];
+        let mut unreadable = vec![];
+        for path in entry_files(journal_path)? {
+            match EntryDescription::read(&path, None) {
+                Ok(ent) => entries.push(ent),
+                Err(_e) => unreadable.push(path.to_string_lossy().into_owned()),
+            }
+        }
+        entries.sort();
+        Ok((Self::from((entries, journal_path.clone())), unreadable))
+    }
     #[must_use]
     pub fn files(&self) -> Vec<PathBuf> {
         self.entries
@@ -58,46 +86,266 @@ impl Book {
             })
             .collect()
     }
+    /// One line per entry, its date and the start of its body, for picking entries.
+    pub fn picker_items(
+        &self,
+        truncate_at: usize,
+        time_format_descriptor: &(impl Formattable + ?Sized),
+    ) -> Result<Vec<String>, Error> {
+        self.entries
+            .iter()
+            .map(|ent| {
+                let body: String = ent
+                    .entry
+                    .body
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .chars()
+                    .take(truncate_at)
+                    .collect();
+                Ok(format!(
+                    "{}  {}",
+                    ent.entry.at.format(time_format_descriptor)?,
+                    body
+                ))
+            })
+            .collect()
+    }
     pub fn table_list(
         &self,
         time_format_descriptor: &(impl Formattable + ?Sized),
     ) -> Result<String, Error> {
-        let mut table = comfy_table::Table::new();
-        table.load_preset(comfy_table::presets::NOTHING);
-
-        table.set_content_arrangement(ContentArrangement::Dynamic);
+        entries_table(
+            self.entries.iter().map(|e| (None, &e.entry)),
+            time_format_descriptor,
+        )
+    }
+    #[must_use]
+    pub fn mood_summary(&self) -> MoodSummary {
+        MoodSummary::from(self.entries.iter().map(|e| &e.entry.mood))
+    }
+    /// Entries written on the same calendar day as the given date in previous years, oldest first.
+    #[must_use]
+    pub fn on_this_day(&self, date: Date, offset: UtcOffset) -> Vec<&EntryDescription> {
         self.entries
             .iter()
-            .try_fold((), |(), entry_desc| -> Result<(), Error> {
-                let bg_color = match entry_desc.entry.mood {
-                    super::Mood::Good => comfy_table::Color::Green,
-                    super::Mood::Bad => comfy_table::Color::Red,
-                    super::Mood::Neutral => comfy_table::Color::White,
+            .filter(|e| {
+                let at = e.entry.at.to_offset(offset).date();
+                at.year() < date.year() && at.month() == date.month() && at.day() == date.day()
+            })
+            .collect()
+    }
+    /// Entries written from the start date up to, but not including, the end date.
+    #[must_use]
+    pub fn between(&self, start: Date, end: Date, offset: UtcOffset) -> Vec<&EntryDescription> {
+        self.entries
+            .iter()
+            .filter(|e| (start..end).contains(&e.entry.at.to_offset(offset).date()))
+            .collect()
+    }
+}
+
+/// An entry, along with the name of the book it is from.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize)]
+pub struct NamedEntry<'a> {
+    pub book: &'a str,
+    #[serde(flatten)]
+    pub entry: &'a Entry,
+}
+
+impl Row for NamedEntry<'_> {
+    fn columns() -> Vec<&'static str> {
+        [vec!["book"], Entry::columns()].concat()
+    }
+    fn cells(&self) -> Vec<String> {
+        [vec![self.book.to_owned()], self.entry.cells()].concat()
+    }
+}
+
+/// Every entry of the books, oldest first, with the name of the book each entry is from.
+#[must_use]
+pub fn merged_entries<'a>(books: &[(&'a str, &'a Book)]) -> Vec<NamedEntry<'a>> {
+    let mut entries: Vec<NamedEntry<'a>> = books
+        .iter()
+        .flat_map(|(name, book)| {
+            book.entries.iter().map(move |e| NamedEntry {
+                book: name,
+                entry: &e.entry,
+            })
+        })
+        .collect();
+    entries.sort_by_key(|e| e.entry.at);
+    entries
+}
+
+/// Every entry of the named books in one table, oldest first, with the name of the book each
+/// entry is from.
+pub fn merged_table_list(
+    books: &[(&str, &Book)],
+    time_format_descriptor: &(impl Formattable + ?Sized),
+) -> Result<String, Error> {
+    entries_table(
+        merged_entries(books)
+            .into_iter()
+            .map(|e| (Some(e.book), e.entry)),
+        time_format_descriptor,
+    )
+}
+
+fn entries_table<'a>(
+    mut entries: impl Iterator<Item = (Option<&'a str>, &'a Entry)>,
+    time_format_descriptor: &(impl Formattable + ?Sized),
+) -> Result<String, Error> {
+    let mut table = comfy_table::Table::new();
+    table.load_preset(comfy_table::presets::NOTHING);
+
+    table.set_content_arrangement(ContentArrangement::Dynamic);
+    entries.try_fold((), |(), (book_name, entry)| -> Result<(), Error> {
+        let mood = &entry.mood;
+        let bg_color = if mood.is_good() {
+            comfy_table::Color::Green
+        } else if mood.is_bad() {
+            comfy_table::Color::Red
+        } else {
+            comfy_table::Color::White
+        };
+        let mut row = vec![];
+        if let Some(book_name) = book_name {
+            row.push(Cell::new(book_name).fg(comfy_table::Color::Cyan));
+        }
+        row.extend([
+            Cell::new((entry.at.format(time_format_descriptor)?).clone())
+                .bg(bg_color)
+                .fg(comfy_table::Color::Black),
+            Cell::new(format!("{mood}")).fg(bg_color),
+            Cell::new(format!("{}", &entry.body)).fg(bg_color),
+            Cell::new(
+                entry
+                    .attachments
+                    .iter()
+                    .map(|a| format!("📎{}", a.name))
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+            )
+            .fg(comfy_table::Color::Blue),
+            // Cell::new(&entry.file_name).fg(comfy_table::Color::Blue),
+            // if entry.entry.tag.is_empty() {
+            //     Cell::new("")
+            // } else {
+            //     Cell::new(
+            //         entry
+            //             .entry
+            //             .tag
+            //             .iter()
+            //             .fold(String::new(), |accu, item| format!("{accu}#{item} "))
+            //             .italic(),
+            //     )
+            //     .fg(comfy_table::Color::Blue)
+            // },
+        ]);
+        table.add_row(row);
+        Ok(())
+    })?;
+
+    Ok(table.to_string())
+}
+
+/// The stretches of time that the journal can be reviewed over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Period {
+    /// From monday to sunday
+    Week,
+    Month,
+}
+
+impl Period {
+    /// The first day of the period that contains the date, and the first day after it.
+    #[must_use]
+    pub fn around(self, date: Date) -> (Date, Date) {
+        match self {
+            Self::Week => {
+                let start =
+                    date - Duration::days(i64::from(date.weekday().number_days_from_monday()));
+                (start, start + Duration::weeks(1))
+            }
+            Self::Month => {
+                let start = date.replace_day(1).unwrap_or(date);
+                let next = start.month().next();
+                let year = if next == Month::January {
+                    start.year() + 1
+                } else {
+                    start.year()
                 };
-                table.add_row(vec![
-                    Cell::new((entry_desc.entry.at.format(time_format_descriptor)?).clone())
-                        .bg(bg_color)
-                        .fg(comfy_table::Color::Black),
-                    Cell::new(format!("{}", &entry_desc.entry.body)).fg(bg_color),
-                    // Cell::new(&entry.file_name).fg(comfy_table::Color::Blue),
-                    // if entry.entry.tag.is_empty() {
-                    //     Cell::new("")
-                    // } else {
-                    //     Cell::new(
-                    //         entry
-                    //             .entry
-                    //             .tag
-                    //             .iter()
-                    //             .fold(String::new(), |accu, item| format!("{accu}#{item} "))
-                    //             .italic(),
-                    //     )
-                    //     .fg(comfy_table::Color::Blue)
-                    // },
-                ]);
-                Ok(())
-            })?;
-
-        Ok(table.to_string())
+                let end = Date::from_calendar_date(year, next, 1).unwrap_or(start);
+                (start, end)
+            }
+        }
+    }
+}
+
+/// Averages and the most felt emotions over a set of entries.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct MoodSummary {
+    pub entries: usize,
+    pub average_score: Option<f64>,
+    pub average_energy: Option<f64>,
+    /// Most frequent first
+    pub emotions: Vec<(String, usize)>,
+}
+
+impl<'a, I> From<I> for MoodSummary
+where
+    I: Iterator<Item = &'a Mood>,
+{
+    fn from(moods: I) -> Self {
+        let mut entries = 0_usize;
+        let mut score_sum = 0_i64;
+        let (mut energy_sum, mut energy_count) = (0_u64, 0_u32);
+        let mut emotions: BTreeMap<String, usize> = BTreeMap::new();
+        for mood in moods {
+            entries += 1;
+            score_sum += i64::from(mood.score);
+            if let Some(energy) = mood.energy {
+                energy_sum += u64::from(energy);
+                energy_count += 1;
+            }
+            for emotion in &mood.emotions {
+                *emotions.entry(emotion.to_lowercase()).or_default() += 1;
+            }
+        }
+        let mut emotions: Vec<(String, usize)> = emotions.into_iter().collect();
+        emotions.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        #[allow(clippy::cast_precision_loss)] // entry counts are nowhere near 2^52
+        Self {
+            entries,
+            average_score: (entries > 0).then(|| score_sum as f64 / entries as f64),
+            average_energy: (energy_count > 0).then(|| energy_sum as f64 / f64::from(energy_count)),
+            emotions,
+        }
+    }
+}
+
+impl Display for MoodSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} entries", self.entries)?;
+        if let Some(score) = self.average_score {
+            write!(f, ", mood {score:+.1}")?;
+        }
+        if let Some(energy) = self.average_energy {
+            write!(f, ", energy {energy:.1}")?;
+        }
+        if !self.emotions.is_empty() {
+            let top: Vec<String> = self
+                .emotions
+                .iter()
+                .take(5)
+                .map(|(emotion, count)| format!("{emotion} ({count})"))
+                .collect();
+            write!(f, ", mostly {}", top.join(", "))?;
+        }
+        Ok(())
     }
 }
 
@@ -109,24 +357,47 @@ impl From<(Vec<EntryDescription>, PathBuf)> for Book {
         }
     }
 }
+/// Every entry file of the journal, attachments are left out even when they are json files.
+pub fn entry_files(journal_path: &Path) -> Result<Vec<PathBuf>, Error> {
+    let attachments = journal_path.join(ATTACHMENTS_DIR);
+    Ok(list_json(journal_path)
+        .map_err(Error::DirCouldNotBeRead)?
+        .into_iter()
+        .filter(|p| !p.starts_with(&attachments))
+        .collect())
+}
+
 impl TryFrom<&PathBuf> for Book {
     type Error = super::Error;
 
     fn try_from(value: &PathBuf) -> Result<Self, Self::Error> {
-        let mut entries = fs_extra::dir::get_dir_content(value)
-            .map_err(Error::DirCouldNotBeRead)?
-            .files
-            .into_iter()
-            .map(PathBuf::from)
-            .filter(is_json)
-            .map(EntryDescription::try_from)
-            .try_fold(vec![], fold_or_err)?;
+        Self::try_from((value, None))
+    }
+}
+impl TryFrom<(&PathBuf, Option<&Keyring>)> for Book {
+    type Error = super::Error;
+
+    /// Entries of an encrypted journal are never cached, the index would give them away.
+    fn try_from((value, keyring): (&PathBuf, Option<&Keyring>)) -> Result<Self, Self::Error> {
+        let mut entries = match keyring {
+            Some(keyring) => entry_files(value)?
+                .into_iter()
+                .map(|p| EntryDescription::read(&p, Some(keyring)))
+                .try_fold(vec![], fold_or_err)?,
+            None => index::load(
+                value,
+                entry_files(value)?,
+                migration::CURRENT_VERSION,
+                |p| EntryDescription::read(p, None),
+            )?,
+        };
         entries.sort();
         Ok(Self::from((entries, value.clone())))
     }
 }
 #[cfg(test)]
 mod testing {
+    #![allow(clippy::unwrap_used)]
     use super::*;
 
     const fn is_normal<T: Sized + Send + Sync + Unpin>() {}
@@ -135,4 +406,153 @@ mod testing {
     const fn normal_types() {
         is_normal::<Book>();
     }
+
+    fn entry_at(at: time::OffsetDateTime) -> EntryDescription {
+        EntryDescription {
+            entry: Entry {
+                schema_version: crate::journal::migration::CURRENT_VERSION,
+                at,
+                body: Arc::new(String::from("body")),
+                tag: vec![],
+                mood: Mood::default(),
+                people: vec![],
+                attachments: vec![],
+                context: None,
+            },
+            file_name: at.unix_timestamp().to_string(),
+        }
+    }
+
+    fn date(year: i32, month: time::Month, day: u8) -> Date {
+        Date::from_calendar_date(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn on_this_day() {
+        let plus_two = UtcOffset::from_hms(2, 0, 0).unwrap();
+        let book = Book::from((
+            vec![
+                entry_at(date(2021, time::Month::October, 24).midnight().assume_utc()),
+                // still the 23rd in utc, but the 24th where the journal is written
+                entry_at(
+                    date(2022, time::Month::October, 23)
+                        .with_hms(23, 0, 0)
+                        .unwrap()
+                        .assume_utc(),
+                ),
+                entry_at(date(2022, time::Month::October, 25).midnight().assume_utc()),
+                entry_at(date(2023, time::Month::October, 24).midnight().assume_utc()),
+            ],
+            PathBuf::new(),
+        ));
+        let found = book.on_this_day(date(2023, time::Month::October, 24), plus_two);
+        assert_eq!(found.len(), 2);
+    }
+
+    #[test]
+    fn picker_items() {
+        let mut ent = entry_at(date(2023, time::Month::October, 24).midnight().assume_utc());
+        ent.entry.body = Arc::new("a long first line\nsecond line".to_owned());
+        let book = Book::from((vec![ent], PathBuf::new()));
+        let format = time::format_description::parse_borrowed::<2>("[year]-[month]-[day]").unwrap();
+        assert_eq!(
+            book.picker_items(6, &format).unwrap(),
+            vec!["2023-10-24  a long".to_owned()]
+        );
+    }
+
+    #[test]
+    fn merged_table_list() {
+        let work = Book::from((
+            vec![
+                entry_at(date(2023, time::Month::October, 22).midnight().assume_utc()),
+                entry_at(date(2023, time::Month::October, 24).midnight().assume_utc()),
+            ],
+            PathBuf::new(),
+        ));
+        let personal = Book::from((
+            vec![entry_at(
+                date(2023, time::Month::October, 23).midnight().assume_utc(),
+            )],
+            PathBuf::new(),
+        ));
+        let format = time::format_description::parse_borrowed::<2>("[year]-[month]-[day]").unwrap();
+        let table =
+            super::merged_table_list(&[("work", &work), ("personal", &personal)], &format).unwrap();
+        let books: Vec<&str> = table
+            .lines()
+            .filter_map(|l| l.split_whitespace().next())
+            .collect();
+        assert_eq!(books, ["work", "personal", "work"]);
+    }
+
+    #[test]
+    fn periods() {
+        let thursday = date(2023, time::Month::February, 16);
+        assert_eq!(
+            Period::Week.around(thursday),
+            (
+                date(2023, time::Month::February, 13),
+                date(2023, time::Month::February, 20)
+            )
+        );
+        assert_eq!(
+            Period::Month.around(thursday),
+            (
+                date(2023, time::Month::February, 1),
+                date(2023, time::Month::March, 1)
+            )
+        );
+    }
+
+    #[test]
+    fn mood_summary() {
+        let moods = [
+            Mood {
+                score: 4,
+                energy: Some(8),
+                emotions: vec!["calm".to_owned(), "Proud".to_owned()],
+            },
+            Mood {
+                score: -1,
+                energy: None,
+                emotions: vec!["calm".to_owned()],
+            },
+        ];
+        let summary = MoodSummary::from(moods.iter());
+        assert_eq!(summary.entries, 2);
+        assert_eq!(summary.average_score, Some(1.5_f64));
+        assert_eq!(summary.average_energy, Some(8.0_f64));
+        assert_eq!(
+            summary.emotions,
+            vec![("calm".to_owned(), 2), ("proud".to_owned(), 1)]
+        );
+        assert_eq!(MoodSummary::from([].iter()).average_score, None);
+    }
+
+    #[test]
+    fn sealed_entries_are_left_out_without_a_keyring() {
+        let journal = tempfile::tempdir().unwrap();
+        let journal_path = journal.path().to_path_buf();
+        let plain = entry_at(time::OffsetDateTime::UNIX_EPOCH);
+        let sealed = entry_at(time::OffsetDateTime::UNIX_EPOCH + time::Duration::days(1));
+        let keyring = Keyring::new(b"correct horse".to_vec());
+        std::fs::write(
+            journal_path.join("plain.json"),
+            plain.entry.to_file_content(None).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(
+            journal_path.join("sealed.json"),
+            sealed.entry.to_file_content(Some(&keyring)).unwrap(),
+        )
+        .unwrap();
+
+        let (book, unreadable) = Book::readable(&journal_path).unwrap();
+        assert_eq!(book.entries.len(), 1);
+        assert_eq!(
+            unreadable,
+            [journal_path.join("sealed.json").to_string_lossy()]
+        );
+    }
 }
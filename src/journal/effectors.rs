@@ -1,19 +1,35 @@
 use super::Result;
-use crate::effects::{CreateDirOpts, EffectKind, EffectMachine, FileWriterOpts, OpenInEditorOpts};
-use crate::files::ToFileName;
+use crate::context::Context;
+use crate::effects::{
+    CreateDirOpts, DraftWriterOpts, EffectKind, EffectMachine, FileWriterOpts, OpenInEditorOpts,
+    RemoveFileOpts,
+};
+use crate::files::{DraftDir, ToFileName};
 use crate::git;
 use crate::journal::entry::Entry;
-use crate::journal::{Book, Error};
+use crate::journal::{
+    entry_files, merged_entries, merged_table_list, migration, AttachmentFile, Book,
+    EntryDescription, Error, Frontmatter, Keyring, MoodSummary, Period, ATTACHMENTS_DIR,
+};
+use crate::links::{self, Backlinks, Link};
+use crate::output;
+use crate::tasks::tasklist::TaskList;
+use color_eyre::owo_colors::OwoColorize;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Confirm, FuzzySelect};
 
 use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::path::{Path, PathBuf};
 use time::formatting::Formattable;
-use time::OffsetDateTime;
+use time::{Date, OffsetDateTime};
 
 pub fn new_entry(
     entry: &Entry,
+    attachments: &[AttachmentFile],
     journal_path: &PathBuf,
     repo_root: &str,
+    keyring: Option<&Keyring>,
     at: OffsetDateTime,
     time_format_descriptor_for_file_name: &(impl Formattable + ?Sized),
 ) -> Result<EffectMachine> {
@@ -30,12 +46,15 @@ pub fn new_entry(
         false,
     );
 
+    let mut entry = entry.clone();
+    entry
+        .attachments
+        .extend(attachments.iter().map(|a| a.attachment.clone()));
+    let mut fp = store_attachments(attachments, journal_path, keyring, &mut effects)?;
+
     effects.add(
         EffectKind::WriteToFile(FileWriterOpts {
-            content: serde_json::to_string_pretty(&entry)
-                .map_err(|e| Error::FileCouldNotSerializeEntryIntoJson(e, file_name.clone()))?
-                .as_bytes()
-                .to_vec(),
+            content: entry.to_file_content(keyring)?,
             file_path: file_path.clone(),
             can_create: true,
             can_overwrite: false,
@@ -43,9 +62,9 @@ pub fn new_entry(
         false,
     );
 
-    let fp = file_path.to_string_lossy().into_owned();
+    fp.push(file_path.to_string_lossy().into_owned());
 
-    effects.add(git::add(repo_root, &[fp]), false);
+    effects.add(git::add(repo_root, &fp), false);
     effects.add(
         git::commit(
             repo_root,
@@ -59,20 +78,525 @@ pub fn new_entry(
     Ok(effects)
 }
 
+/// Writes the template into the draft file and opens it in the editor, the draft can be turned
+/// into an entry with [`new_entry_from_draft`] after the editor exits. The draft belongs in a
+/// [`crate::files::DraftDir`], which removes it.
+pub fn compose_entry(
+    template: &Frontmatter,
+    draft_path: &Path,
+    editor: String,
+) -> Result<EffectMachine> {
+    let mut effects = EffectMachine::default();
+
+    effects.add(
+        EffectKind::WriteToDraft(DraftWriterOpts {
+            content: template.render().into_bytes(),
+            file_path: draft_path.to_path_buf(),
+        }),
+        false,
+    );
+    effects.add(
+        EffectKind::OpenInEditor(OpenInEditorOpts {
+            editor,
+            files_to_edit: vec![draft_path.to_path_buf()],
+        }),
+        false,
+    );
+
+    Ok(effects)
+}
+
+/// Parses the draft written by [`compose_entry`] and saves it like [`new_entry`] does.
+#[allow(clippy::too_many_arguments)] // what new_entry takes, and the draft
+pub fn new_entry_from_draft(
+    draft_path: &Path,
+    attachments: &[AttachmentFile],
+    journal_path: &PathBuf,
+    repo_root: &str,
+    keyring: Option<&Keyring>,
+    context: Option<Context>,
+    at: OffsetDateTime,
+    time_format_descriptor_for_file_name: &(impl Formattable + ?Sized),
+) -> Result<EffectMachine> {
+    let content = fs_extra::file::read_to_string(draft_path).map_err(Error::FileCouldNotBeRead)?;
+    let entry = Entry {
+        context,
+        ..Frontmatter::parse(&content)?.into_entry(at)?
+    };
+
+    new_entry(
+        &entry,
+        attachments,
+        journal_path,
+        repo_root,
+        keyring,
+        at,
+        time_format_descriptor_for_file_name,
+    )
+}
+
+/// Adds the files to the attachments of the single entry whose file name contains the specifier.
+pub fn attach_files(
+    book: &Book,
+    specifier: &str,
+    attachments: &[AttachmentFile],
+    repo_root: &str,
+    keyring: Option<&Keyring>,
+) -> Result<EffectMachine> {
+    let mut effects = EffectMachine::default();
+
+    let ent = single_entry(book, specifier)?;
+
+    let mut entry = ent.entry.clone();
+    for file in attachments {
+        if !entry.attachments.contains(&file.attachment) {
+            entry.attachments.push(file.attachment.clone());
+        }
+    }
+    let mut fp = store_attachments(attachments, &book.location, keyring, &mut effects)?;
+
+    let file_path = book.location.join(&ent.file_name);
+    effects.add(
+        EffectKind::WriteToFile(FileWriterOpts {
+            content: entry.to_file_content(keyring)?,
+            file_path: file_path.clone(),
+            can_create: false,
+            can_overwrite: true,
+        }),
+        false,
+    );
+    fp.push(file_path.to_string_lossy().into_owned());
+
+    effects.add(git::add(repo_root, &fp), false);
+    effects.add(
+        git::commit(
+            repo_root,
+            &format!(
+                "feat(journal): attach {} files to the entry {}",
+                attachments.len(),
+                ent.file_name
+            ),
+        ),
+        false,
+    );
+    effects.add(git::pull(repo_root), false);
+    effects.add(git::push(repo_root), false);
+
+    Ok(effects)
+}
+
+/// Writes the attachments of the single entry whose file name contains the specifier into the
+/// directory under their own names, decrypted if they were sealed. Files that are already there
+/// are left alone.
+pub fn export_attachments(
+    book: &Book,
+    specifier: &str,
+    to: &Path,
+    keyring: Option<&Keyring>,
+) -> Result<EffectMachine> {
+    let mut effects = EffectMachine::default();
+
+    let ent = single_entry(book, specifier)?;
+    if ent.entry.attachments.is_empty() {
+        effects.add(
+            EffectKind::PrintToStdOut(format!("{} has no attachments", ent.file_name)),
+            false,
+        );
+        return Ok(effects);
+    }
+
+    effects.add(
+        EffectKind::CreateDir(CreateDirOpts {
+            folder_path: to.to_path_buf(),
+            ok_if_exists: true,
+        }),
+        false,
+    );
+    for attachment in &ent.entry.attachments {
+        effects.add(
+            EffectKind::WriteToFile(FileWriterOpts {
+                content: attachment.read(&book.location, keyring)?,
+                file_path: to.join(&attachment.name),
+                can_create: true,
+                can_overwrite: false,
+            }),
+            true,
+        );
+    }
+
+    Ok(effects)
+}
+
+/// Prints the entry, along with the tasks and entries it links to and the entries that link to it.
+pub fn show_entry(
+    book: &Book,
+    specifier: &str,
+    tasks_list: Option<&TaskList>,
+    time_format_descriptor_for_displaying: &(impl Formattable + ?Sized),
+) -> Result<EffectMachine> {
+    let mut efs = EffectMachine::default();
+
+    let ent = single_entry(book, specifier)?;
+    efs.add(
+        EffectKind::PrintToStdOut(
+            ent.entry
+                .pretty_formated(time_format_descriptor_for_displaying)?,
+        ),
+        false,
+    );
+
+    let linked: Vec<String> = links::parse(&ent.entry.body)
+        .into_iter()
+        .map(|link| match link {
+            Link::Task(id) => match tasks_list
+                .and_then(|tl| tl.0.iter().find(|t| t.task.id == id))
+                .map(|t| &t.task)
+            {
+                Some(task) => format!(
+                    "→ task {id} {} {}",
+                    task.current_state()
+                        .map(ToString::to_string)
+                        .unwrap_or_default(),
+                    task.title
+                ),
+                None => format!("→ task {id}"),
+            },
+            Link::Entry(name) => format!("→ entry {name}"),
+        })
+        .collect();
+    if !linked.is_empty() {
+        efs.add(EffectKind::PrintToStdOut(linked.join("\n")), false);
+    }
+
+    let mentions: Vec<String> = Backlinks::from(book)
+        .to_entry(&ent.file_name)
+        .iter()
+        .map(|file_name| format!("← entry {file_name}"))
+        .collect();
+    if !mentions.is_empty() {
+        efs.add(EffectKind::PrintToStdOut(mentions.join("\n")), false);
+    }
+
+    Ok(efs)
+}
+
+/// The only entry whose file name contains the specifier.
+fn single_entry<'a>(book: &'a Book, specifier: &str) -> Result<&'a EntryDescription> {
+    let matching: Vec<&EntryDescription> = book
+        .entries
+        .iter()
+        .filter(|x| x.file_name.contains(specifier))
+        .collect();
+    match matching.as_slice() {
+        [] => Err(Error::EntryCouldNotBeFound),
+        [ent] => Ok(ent),
+        more => Err(Error::EntryIsAmbiguous(more.len())),
+    }
+}
+
+/// Writes the attachments that are not stored yet, and gives back every attachment file so they
+/// can be staged along with the entry.
+fn store_attachments(
+    attachments: &[AttachmentFile],
+    journal_path: &Path,
+    keyring: Option<&Keyring>,
+    effects: &mut EffectMachine,
+) -> Result<Vec<String>> {
+    if attachments.is_empty() {
+        return Ok(vec![]);
+    }
+    effects.add(
+        EffectKind::CreateDir(CreateDirOpts {
+            folder_path: journal_path.join(ATTACHMENTS_DIR),
+            ok_if_exists: true,
+        }),
+        false,
+    );
+
+    let mut stored = HashSet::new();
+    let mut file_paths = vec![];
+    for file in attachments {
+        let file_path = file.attachment.stored_path(journal_path, keyring.is_some());
+        if !stored.insert(file_path.clone()) {
+            continue;
+        }
+        if !file_path.exists() {
+            effects.add(
+                EffectKind::WriteToFile(FileWriterOpts {
+                    content: match keyring {
+                        Some(keyring) => keyring.seal(&file.content)?,
+                        None => file.content.clone(),
+                    },
+                    file_path: file_path.clone(),
+                    can_create: true,
+                    can_overwrite: false,
+                }),
+                false,
+            );
+        }
+        file_paths.push(file_path.to_string_lossy().into_owned());
+    }
+    Ok(file_paths)
+}
+
+/// Writes every entry that the book does not have yet, entries are considered the same if they are
+/// at the same moment. Entries that would get the file name of another one get a number after
+/// their file name.
+pub fn import_entries(
+    entries: Vec<Entry>,
+    book: &Book,
+    journal_path: &Path,
+    repo_root: &str,
+    keyring: Option<&Keyring>,
+    time_format_descriptor_for_file_name: &(impl Formattable + ?Sized),
+) -> Result<EffectMachine> {
+    let mut effects = EffectMachine::default();
+
+    let mut known: HashSet<OffsetDateTime> = book.entries.iter().map(|e| e.entry.at).collect();
+    let (new_entries, skipped): (Vec<Entry>, Vec<Entry>) = entries
+        .into_iter()
+        .partition(|entry| known.insert(entry.at));
+
+    effects.add(
+        EffectKind::PrintToStdOut(format!(
+            "importing {} entries, skipping {} duplicates",
+            new_entries.len(),
+            skipped.len()
+        )),
+        false,
+    );
+    for entry in &skipped {
+        effects.add(
+            EffectKind::PrintToStdOut(format!(
+                "skipped {} \"{}\", the journal already has an entry at that moment",
+                entry.to_file_name(time_format_descriptor_for_file_name)?,
+                entry.body.lines().next().unwrap_or_default()
+            )),
+            false,
+        );
+    }
+    if new_entries.is_empty() {
+        return Ok(effects);
+    }
+
+    effects.add(
+        EffectKind::CreateDir(CreateDirOpts {
+            folder_path: journal_path.to_owned(),
+            ok_if_exists: true,
+        }),
+        false,
+    );
+
+    let mut taken: HashSet<String> = book.entries.iter().map(|e| e.file_name.clone()).collect();
+    let mut file_paths = vec![];
+    for entry in &new_entries {
+        let file_name = unique_file_name(
+            &entry.to_file_name(time_format_descriptor_for_file_name)?,
+            &mut taken,
+        );
+        let file_path = journal_path.join(&file_name);
+        effects.add(
+            EffectKind::WriteToFile(FileWriterOpts {
+                content: entry.to_file_content(keyring)?,
+                file_path: file_path.clone(),
+                can_create: true,
+                can_overwrite: false,
+            }),
+            false,
+        );
+        file_paths.push(file_path.to_string_lossy().into_owned());
+    }
+
+    effects.add(git::add(repo_root, &file_paths), false);
+    effects.add(
+        git::commit(
+            repo_root,
+            &format!("feat(journal): import {} entries", file_paths.len()),
+        ),
+        false,
+    );
+    effects.add(git::pull(repo_root), false);
+    effects.add(git::push(repo_root), false);
+
+    Ok(effects)
+}
+
+/// The file name, or the same with `-2`, `-3` and so on before the extension when it is taken.
+fn unique_file_name(file_name: &str, taken: &mut HashSet<String>) -> String {
+    let path = Path::new(file_name);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()));
+    let mut candidate = file_name.to_owned();
+    let mut number = 1_u32;
+    while !taken.insert(candidate.clone()) {
+        number += 1;
+        candidate = format!(
+            "{stem}-{number}{}",
+            extension.as_deref().unwrap_or_default()
+        );
+    }
+    candidate
+}
+
+/// Rewrites every entry file that was written with an older schema, in a single commit.
+pub fn migrate_entries(
+    journal_path: &Path,
+    repo_root: &str,
+    keyring: Option<&Keyring>,
+) -> Result<EffectMachine> {
+    Ok(match keyring {
+        Some(keyring) => crate::migration::rewrite_outdated::<Entry>(
+            &migration::REGISTRY,
+            entry_files(journal_path)?,
+            repo_root,
+            keyring,
+        )?,
+        None => crate::migration::rewrite_outdated::<Entry>(
+            &migration::REGISTRY,
+            entry_files(journal_path)?,
+            repo_root,
+            &crate::migration::Plain,
+        )?,
+    })
+}
+
+pub fn mood_stats(book: &Book) -> EffectMachine {
+    let mut efs = EffectMachine::default();
+    efs.add(
+        EffectKind::PrintToStdOut(book.mood_summary().to_string()),
+        false,
+    );
+    efs
+}
+
+/// Prints the entries written on this day in previous years, with how long ago they were.
+pub fn on_this_day(
+    book: &Book,
+    now: OffsetDateTime,
+    time_format_descriptor_for_displaying: &(impl Formattable + ?Sized),
+) -> Result<EffectMachine> {
+    let mut efs = EffectMachine::default();
+
+    let entries = book.on_this_day(now.date(), now.offset());
+    if entries.is_empty() {
+        efs.add(
+            EffectKind::PrintToStdOut("nothing was written on this day before".to_owned()),
+            false,
+        );
+        return Ok(efs);
+    }
+    for ent in entries {
+        let years_ago = now.year() - ent.entry.at.to_offset(now.offset()).year();
+        efs.add(
+            EffectKind::PrintToStdOut(format!(
+                "{}\n{}\n",
+                format!("{years_ago} years ago")
+                    .bold()
+                    .black()
+                    .on_bright_blue(),
+                ent.entry
+                    .pretty_formated(time_format_descriptor_for_displaying)?
+            )),
+            false,
+        );
+    }
+
+    Ok(efs)
+}
+
+/// Prints every entry of the period around now grouped by day, with the moods of each day and of
+/// the whole period.
+pub fn review(
+    book: &Book,
+    period: Period,
+    now: OffsetDateTime,
+    time_format_descriptor_for_displaying: &(impl Formattable + ?Sized),
+) -> Result<EffectMachine> {
+    let mut efs = EffectMachine::default();
+
+    let (start, end) = period.around(now.date());
+    let entries = book.between(start, end, now.offset());
+
+    let mut days: BTreeMap<Date, Vec<&EntryDescription>> = BTreeMap::new();
+    for ent in &entries {
+        days.entry(ent.entry.at.to_offset(now.offset()).date())
+            .or_default()
+            .push(ent);
+    }
+    for (day, day_entries) in days {
+        efs.add(
+            EffectKind::PrintToStdOut(format!(
+                "{}  {}",
+                format!("{} {}", day, day.weekday())
+                    .bold()
+                    .black()
+                    .on_bright_blue(),
+                MoodSummary::from(day_entries.iter().map(|e| &e.entry.mood)).dimmed()
+            )),
+            false,
+        );
+        for ent in day_entries {
+            efs.add(
+                EffectKind::PrintToStdOut(format!(
+                    "{}\n",
+                    ent.entry
+                        .pretty_formated(time_format_descriptor_for_displaying)?
+                )),
+                false,
+            );
+        }
+    }
+    efs.add(
+        EffectKind::PrintToStdOut(format!(
+            "{} to {}: {}",
+            start,
+            end.previous_day().unwrap_or(end),
+            MoodSummary::from(entries.iter().map(|e| &e.entry.mood))
+        )),
+        false,
+    );
+
+    Ok(efs)
+}
+
 pub fn list_entries(
     book: &Book,
+    format: output::Format,
     time_format_descriptor: &(impl Formattable + ?Sized),
 ) -> Result<EffectMachine> {
     let mut efs = EffectMachine::default();
 
+    let entries: Vec<&Entry> = book.entries.iter().map(|e| &e.entry).collect();
     efs.add(
-        EffectKind::PrintToStdOut(book.table_list(time_format_descriptor)?.clone()),
+        EffectKind::PrintToStdOut(match output::render(&entries, format)? {
+            Some(rendered) => rendered,
+            None => book.table_list(time_format_descriptor)?,
+        }),
         false,
     );
 
     Ok(efs)
 }
 
+/// The entries of several books in one list, with the name of the book each entry is from.
+pub fn list_books(
+    books: &[(&str, &Book)],
+    format: output::Format,
+    time_format_descriptor: &(impl Formattable + ?Sized),
+) -> Result<EffectMachine> {
+    let mut efs = EffectMachine::default();
+    efs.add(
+        EffectKind::PrintToStdOut(match output::render(&merged_entries(books), format)? {
+            Some(rendered) => rendered,
+            None => merged_table_list(books, time_format_descriptor)?,
+        }),
+        false,
+    );
+    Ok(efs)
+}
+
 pub fn edit_last_entry(
     journal_path: &Path,
     book: &Book,
@@ -109,19 +633,16 @@ pub fn edit_last_entry(
     Ok(effects)
 }
 
-pub fn edit_specific_entry(
+pub fn edit_entries(
     journal_path: &Path,
-    specifier: &str,
-    book: &Book,
+    entries: &[&EntryDescription],
     repo_root: &str,
     editor: String,
 ) -> Result<EffectMachine> {
     let mut effects = EffectMachine::default();
 
-    let ent_path: Vec<PathBuf> = book
-        .entries
+    let ent_path: Vec<PathBuf> = entries
         .iter()
-        .filter(|x| x.file_name.contains(specifier))
         .map(|ent| journal_path.join(&ent.file_name))
         .collect();
 
@@ -150,50 +671,241 @@ pub fn edit_specific_entry(
     Ok(effects)
 }
 
-// pub fn delete_interactive(
-//     journal_path: &PathBuf,
-//     truncation_amount: usize,
-//     book: Book,
-//     time_format_descriptor_for_displaying: &(impl Formattable + ?Sized),
-// ) -> Result<()> {
-//     let options: Vec<String> = book.truncated_form(truncation_amount);
-
-//     let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
-//         .with_prompt("which file")
-//         .items(&options)
-//         .interact()
-//         .map_err(Error::DialoguerError)?;
-
-//     println!("\n{}", "This is the selected item:".bold().red());
-
-//     let selected = book
-//         .entries
-//         .get(selection)
-//         .ok_or(Error::EntryCouldNotBeFound)?;
-
-//     println!(
-//         "{}\n",
-//         selected
-//             .entry
-//             .pretty_formated(time_format_descriptor_for_displaying)?
-//     );
-
-//     if Confirm::with_theme(&ColorfulTheme::default())
-//         .with_prompt(format!(
-//             "Are you {} you want to {} the above entry?",
-//             "absolutely sure".bold().red(),
-//             "delete".bold().red()
-//         ))
-//         .default(false)
-//         .interact()
-//         .map_err(Error::DialoguerError)?
-//     {
-//         let file_path = journal_path.join(&selected.file_name);
-//         fs_extra::remove_items(&[file_path]).map_err(Error::FileCouldNotBeDeleted)?;
-//     };
-
-//     Ok(())
-// }
+/// A decrypted copy of a sealed entry, that lives only while the entry is being edited.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Draft {
+    pub draft_path: PathBuf,
+    pub file_path: PathBuf,
+}
+
+/// Writes decrypted copies of the entries into the draft directory and opens them in the editor,
+/// they are sealed back with [`save_sealed_drafts`] after the editor exits. The copies go away
+/// with the draft directory.
+pub fn edit_sealed_entries(
+    entries: &[&EntryDescription],
+    journal_path: &Path,
+    draft_dir: &DraftDir,
+    editor: String,
+) -> Result<(EffectMachine, Vec<Draft>)> {
+    let mut effects = EffectMachine::default();
+
+    if entries.is_empty() {
+        return Err(Error::NoEntries);
+    }
+
+    let mut drafts = vec![];
+    for ent in entries {
+        let draft = Draft {
+            draft_path: draft_dir.path().join(&ent.file_name),
+            file_path: journal_path.join(&ent.file_name),
+        };
+        effects.add(
+            EffectKind::WriteToDraft(DraftWriterOpts {
+                content: ent.entry.to_file_content(None)?,
+                file_path: draft.draft_path.clone(),
+            }),
+            false,
+        );
+        drafts.push(draft);
+    }
+
+    effects.add(
+        EffectKind::OpenInEditor(OpenInEditorOpts {
+            editor,
+            files_to_edit: drafts.iter().map(|d| d.draft_path.clone()).collect(),
+        }),
+        false,
+    );
+
+    Ok((effects, drafts))
+}
+
+/// Seals the edited drafts back into their entries.
+pub fn save_sealed_drafts(
+    drafts: &[Draft],
+    repo_root: &str,
+    keyring: &Keyring,
+) -> Result<EffectMachine> {
+    let mut effects = EffectMachine::default();
+
+    for draft in drafts {
+        let entry = Entry::read(&draft.draft_path, None)?;
+        effects.add(
+            EffectKind::WriteToFile(FileWriterOpts {
+                content: entry.to_file_content(Some(keyring))?,
+                file_path: draft.file_path.clone(),
+                can_create: false,
+                can_overwrite: true,
+            }),
+            false,
+        );
+    }
+
+    let fp: Vec<String> = drafts
+        .iter()
+        .map(|d| d.file_path.to_string_lossy().into_owned())
+        .collect();
+
+    effects.add(git::add(repo_root, &fp), false);
+    effects.add(
+        git::commit(repo_root, "feat(journal): edit the few entries"),
+        false,
+    );
+    effects.add(git::pull(repo_root), false);
+    effects.add(git::push(repo_root), false);
+
+    Ok(effects)
+}
+
+/// Lets the user pick one, or with `multiple` any number of, entries by their date and the start
+/// of their body.
+pub fn pick_entries<'a>(
+    book: &'a Book,
+    multiple: bool,
+    truncation_amount: usize,
+    time_format_descriptor_for_displaying: &(impl Formattable + ?Sized),
+) -> Result<Vec<&'a EntryDescription>> {
+    let options = book.picker_items(truncation_amount, time_format_descriptor_for_displaying)?;
+
+    let selections = if multiple {
+        pick_many(&options)?
+    } else {
+        vec![FuzzySelect::with_theme(&ColorfulTheme::default())
+            .with_prompt("which file")
+            .items(&options)
+            .interact()
+            .map_err(Error::DialoguerError)?]
+    };
+
+    selections
+        .into_iter()
+        .map(|i| book.entries.get(i).ok_or(Error::EntryCouldNotBeFound))
+        .collect()
+}
+
+/// Fuzzy searches the options again and again, each pick toggles whether an option is in, until
+/// the first option, or escape, says that is all.
+fn pick_many(options: &[String]) -> Result<Vec<usize>> {
+    let mut picked = BTreeSet::new();
+    let mut last = 0;
+    loop {
+        let items: Vec<String> = std::iter::once(format!("done, with {} picked", picked.len()))
+            .chain(options.iter().enumerate().map(|(i, option)| {
+                format!("[{}] {option}", if picked.contains(&i) { "x" } else { " " })
+            }))
+            .collect();
+        match FuzzySelect::with_theme(&ColorfulTheme::default())
+            .with_prompt("which files, type to search and enter to pick or unpick")
+            .items(&items)
+            .default(last)
+            .interact_opt()
+            .map_err(Error::DialoguerError)?
+        {
+            None | Some(0) => return Ok(picked.into_iter().collect()),
+            Some(i) => {
+                if !picked.remove(&(i - 1)) {
+                    picked.insert(i - 1);
+                }
+                last = i;
+            }
+        }
+    }
+}
+
+/// Asks before opening more than `max_files` files at once, fewer are opened without asking.
+pub fn confirm_opening(count: usize, max_files: usize) -> Result<bool> {
+    if count <= max_files {
+        return Ok(true);
+    }
+    Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!(
+            "This will open {} files, do you want to continue?",
+            count.bold().red()
+        ))
+        .default(false)
+        .interact()
+        .map_err(Error::DialoguerError)
+}
+
+/// Lets the user pick an entry, shows it and asks for confirmation before deleting it with
+/// [`delete_entry`].
+pub fn delete_interactive(
+    truncation_amount: usize,
+    book: &Book,
+    repo_root: &str,
+    time_format_descriptor_for_displaying: &(impl Formattable + ?Sized),
+) -> Result<EffectMachine> {
+    let options: Vec<String> = book.truncated_form(truncation_amount);
+
+    let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("which file")
+        .items(&options)
+        .interact()
+        .map_err(Error::DialoguerError)?;
+
+    let selected = book
+        .entries
+        .get(selection)
+        .ok_or(Error::EntryCouldNotBeFound)?;
+
+    let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!(
+            "{}\n{}\n\nAre you {} you want to {} the above entry?",
+            "This is the selected item:".bold().red(),
+            selected
+                .entry
+                .pretty_formated(time_format_descriptor_for_displaying)?,
+            "absolutely sure".bold().red(),
+            "delete".bold().red()
+        ))
+        .default(false)
+        .interact()
+        .map_err(Error::DialoguerError)?;
+
+    if !confirmed {
+        let mut effects = EffectMachine::default();
+        effects.add(
+            EffectKind::PrintToStdOut("nothing was deleted".to_owned()),
+            false,
+        );
+        return Ok(effects);
+    }
+
+    delete_entry(book, &selected.file_name, repo_root)
+}
+
+/// Removes the only entry whose file name contains the specifier, and commits the removal.
+pub fn delete_entry(book: &Book, specifier: &str, repo_root: &str) -> Result<EffectMachine> {
+    let mut effects = EffectMachine::default();
+
+    let ent = single_entry(book, specifier)?;
+    let file_path = book.location.join(&ent.file_name);
+
+    effects.add(
+        EffectKind::RemoveFile(RemoveFileOpts {
+            file_path: file_path.clone(),
+            ok_if_missing: false,
+            shred: false,
+        }),
+        false,
+    );
+
+    let fp = file_path.to_string_lossy().into_owned();
+
+    effects.add(git::add(repo_root, &[fp]), false);
+    effects.add(
+        git::commit(
+            repo_root,
+            &format!("feat(journal): delete the entry {}", ent.file_name),
+        ),
+        false,
+    );
+    effects.add(git::pull(repo_root), false);
+    effects.add(git::push(repo_root), false);
+
+    Ok(effects)
+}
+
 pub fn edit_all_entries(editor: String, book: &Book, repo_root: &str) -> Result<EffectMachine> {
     let mut effects = EffectMachine::default();
 
@@ -218,10 +930,10 @@ pub fn edit_all_entries(editor: String, book: &Book, repo_root: &str) -> Result<
 
 #[cfg(test)]
 mod testing {
-    #[allow(clippy::unwrap_used)]
+    #![allow(clippy::unwrap_used)]
     use std::sync::Arc;
 
-    use crate::journal::Mood;
+    use crate::journal::{EntryDescription, Mood};
 
     #[allow(clippy::wildcard_imports)]
     use super::*;
@@ -232,16 +944,19 @@ mod testing {
     fn entry() -> Entry {
         let now = time::OffsetDateTime::now_utc();
         let body = Arc::new(String::from("body"));
-        let mood = Mood::Good;
+        let mood = Mood::from_score(Mood::GOOD);
         let tag = vec!["tag1".to_owned(), "tag2".to_owned()];
         let people = vec!["hoverbear".to_owned(), "mr_leafslug".to_owned()];
 
         Entry {
+            schema_version: migration::CURRENT_VERSION,
             at: now,
             body,
             tag,
             mood,
             people,
+            attachments: vec![],
+            context: None,
         }
     }
     #[fixture]
@@ -257,7 +972,244 @@ mod testing {
         #[case] repo_root: &str,
         #[case] at: OffsetDateTime,
     ) {
-        let em = new_entry(&entry, &journal_path, repo_root, at, &well_known::Rfc3339).unwrap();
+        let em = new_entry(
+            &entry,
+            &[],
+            &journal_path,
+            repo_root,
+            None,
+            at,
+            &well_known::Rfc3339,
+        )
+        .unwrap();
         assert_eq!(em.0.len(), 6);
     }
+
+    #[rstest]
+    fn test_new_with_attachments(entry: Entry, journal_path: PathBuf) {
+        let photo = AttachmentFile::new("photo.jpg".to_owned(), b"pixels".to_vec());
+        let em = new_entry(
+            &entry,
+            &[photo.clone(), photo.clone()],
+            &journal_path,
+            "somerepo",
+            None,
+            entry.at,
+            &well_known::Rfc3339,
+        )
+        .unwrap();
+        // the same photo is written once, next to the entry and its six effects
+        assert_eq!(em.0.len(), 8);
+        let stored = photo
+            .attachment
+            .stored_path(&journal_path, false)
+            .to_string_lossy()
+            .into_owned();
+        assert!(em.0.iter().any(|e| matches!(
+            &e.effect_kind,
+            EffectKind::RunExternalCommand(_, args, _) if args.contains(&stored)
+        )));
+    }
+
+    #[rstest]
+    fn test_attach_needs_a_single_entry(entry: Entry, journal_path: PathBuf) {
+        let book = Book::from((
+            vec![
+                EntryDescription {
+                    entry: entry.clone(),
+                    file_name: "2023-10-24.json".to_owned(),
+                },
+                EntryDescription {
+                    entry,
+                    file_name: "2023-10-25.json".to_owned(),
+                },
+            ],
+            journal_path,
+        ));
+        let photo = AttachmentFile::new("photo.jpg".to_owned(), b"pixels".to_vec());
+        let attach = |specifier| {
+            attach_files(
+                &book,
+                specifier,
+                std::slice::from_ref(&photo),
+                "somerepo",
+                None,
+            )
+        };
+        assert!(matches!(attach("2023-10"), Err(Error::EntryIsAmbiguous(2))));
+        assert!(matches!(attach("2022"), Err(Error::EntryCouldNotBeFound)));
+        // create dir, the attachment, the entry and the four git effects
+        assert_eq!(attach("10-24").unwrap().0.len(), 7);
+    }
+
+    #[rstest]
+    fn test_delete(entry: Entry, journal_path: PathBuf) {
+        let book = Book::from((
+            vec![EntryDescription {
+                entry,
+                file_name: "2023-10-24.json".to_owned(),
+            }],
+            journal_path.clone(),
+        ));
+        let em = delete_entry(&book, "10-24", "somerepo").unwrap();
+        // remove and the four git effects
+        assert_eq!(em.0.len(), 5);
+        assert!(matches!(
+            &em.0.first().unwrap().effect_kind,
+            EffectKind::RemoveFile(RemoveFileOpts { file_path, ok_if_missing: false, .. })
+                if file_path == &journal_path.join("2023-10-24.json")
+        ));
+        assert!(delete_entry(&book, "2022", "somerepo").is_err());
+    }
+
+    #[rstest]
+    fn test_new_sealed(entry: Entry, journal_path: PathBuf) {
+        let keyring = Keyring::new(b"correct horse".to_vec());
+        let em = new_entry(
+            &entry,
+            &[],
+            &journal_path,
+            "somerepo",
+            Some(&keyring),
+            entry.at,
+            &well_known::Rfc3339,
+        )
+        .unwrap();
+        let content =
+            em.0.iter()
+                .find_map(|e| match &e.effect_kind {
+                    EffectKind::WriteToFile(opts) => String::from_utf8(opts.content.clone()).ok(),
+                    _ => None,
+                })
+                .unwrap();
+        assert!(!content.contains("hoverbear"));
+        assert_eq!(
+            migration::REGISTRY
+                .read::<Entry>(&keyring.open(&content).unwrap())
+                .unwrap(),
+            entry
+        );
+    }
+
+    #[rstest]
+    fn test_edit_sealed_drafts(entry: Entry, journal_path: PathBuf) {
+        let ent = EntryDescription {
+            entry,
+            file_name: "existing.json".to_owned(),
+        };
+        let draft_dir = DraftDir::new("jnl-test-").unwrap();
+        let (em, drafts) =
+            edit_sealed_entries(&[&ent], &journal_path, &draft_dir, "vi".to_owned()).unwrap();
+        // a single draft and the editor
+        assert_eq!(em.0.len(), 2);
+        assert!(matches!(
+            &em.0.first().unwrap().effect_kind,
+            EffectKind::WriteToDraft(_)
+        ));
+        assert_eq!(
+            drafts,
+            vec![Draft {
+                draft_path: draft_dir.path().join("existing.json"),
+                file_path: journal_path.join("existing.json"),
+            }]
+        );
+    }
+
+    #[rstest]
+    fn test_import_skips_duplicates(entry: Entry, journal_path: PathBuf) {
+        let book = Book::from((
+            vec![EntryDescription {
+                entry: entry.clone(),
+                file_name: "existing.json".to_owned(),
+            }],
+            journal_path.clone(),
+        ));
+        let mut other = entry.clone();
+        other.at += time::Duration::hours(1);
+
+        let em = import_entries(
+            vec![entry, other.clone(), other],
+            &book,
+            &journal_path,
+            "somerepo",
+            None,
+            &well_known::Rfc3339,
+        )
+        .unwrap();
+        // print, two skipped, create dir, a single write and the four git effects
+        assert_eq!(em.0.len(), 9);
+    }
+
+    #[rstest]
+    fn test_import_same_day(mut entry: Entry, journal_path: PathBuf) {
+        entry.at = entry
+            .at
+            .replace_time(time::Time::from_hms(12, 0, 0).unwrap());
+        let file_name_format =
+            time::format_description::parse("[year]-[month]-[day].json").unwrap();
+        let book = Book::from((
+            vec![EntryDescription {
+                entry: entry.clone(),
+                file_name: entry.to_file_name(&file_name_format).unwrap(),
+            }],
+            journal_path.clone(),
+        ));
+        let mut other = entry.clone();
+        other.at += time::Duration::seconds(1);
+
+        let em = import_entries(
+            vec![other],
+            &book,
+            &journal_path,
+            "somerepo",
+            None,
+            &file_name_format,
+        )
+        .unwrap();
+        let written: Vec<&PathBuf> =
+            em.0.iter()
+                .filter_map(|e| match &e.effect_kind {
+                    EffectKind::WriteToFile(opts) => Some(&opts.file_path),
+                    _ => None,
+                })
+                .collect();
+        assert_eq!(
+            written,
+            [&journal_path.join(format!("{}-2.json", entry.at.date()))]
+        );
+    }
+
+    #[rstest]
+    #[case::free("2023-10-24.json", "2023-10-24.json")]
+    #[case::taken("2023-10-23.json", "2023-10-23-3.json")]
+    #[case::no_extension("2023-10-23", "2023-10-23-2")]
+    fn unique_file_names(#[case] file_name: &str, #[case] expected: &str) {
+        let mut taken: HashSet<String> = ["2023-10-23.json", "2023-10-23-2.json", "2023-10-23"]
+            .into_iter()
+            .map(ToOwned::to_owned)
+            .collect();
+        assert_eq!(unique_file_name(file_name, &mut taken), expected);
+    }
+
+    #[rstest]
+    fn test_import_nothing_new(entry: Entry, journal_path: PathBuf) {
+        let book = Book::from((
+            vec![EntryDescription {
+                entry: entry.clone(),
+                file_name: "existing.json".to_owned(),
+            }],
+            journal_path.clone(),
+        ));
+        let em = import_entries(
+            vec![entry],
+            &book,
+            &journal_path,
+            "somerepo",
+            None,
+            &well_known::Rfc3339,
+        )
+        .unwrap();
+        // print and the skipped one
+        assert_eq!(em.0.len(), 2);
+    }
 }
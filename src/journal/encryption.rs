@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+
+use crate::migration;
+
+use super::Error;
+
+const SALT_LENGTH: usize = 16;
+const ENVELOPE_VERSION: u32 = 1;
+
+type Salt = [u8; SALT_LENGTH];
+
+/// Where the secret of an encrypted journal comes from, a key file wins over a passphrase.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Config {
+    pub enabled: bool,
+    /// A file whose whole content is the secret
+    pub key_file: Option<PathBuf>,
+    /// The environment variable holding the passphrase, it is asked for interactively if unset
+    pub passphrase_env: Option<String>,
+}
+
+/// Seals and opens entry files, keys are derived from the secret with argon2 and a salt that is
+/// stored next to the ciphertext, with a nonce of its own for every file.
+///
+/// Everything sealed by the same keyring shares one salt, picked when the keyring is made, so a
+/// session only derives a handful of keys no matter how many entries it touches.
+pub struct Keyring {
+    secret: Vec<u8>,
+    salt: Salt,
+    keys: Mutex<HashMap<Salt, Key>>,
+}
+
+impl Debug for Keyring {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Keyring").finish_non_exhaustive()
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Envelope {
+    encrypted: Sealed,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Sealed {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl Keyring {
+    #[must_use]
+    pub fn new(secret: Vec<u8>) -> Self {
+        let mut salt = Salt::default();
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            secret,
+            salt,
+            keys: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_key_file(key_file: &PathBuf) -> Result<Self, Error> {
+        let secret = std::fs::read(key_file).map_err(Error::KeyFileCouldNotBeRead)?;
+        if secret.is_empty() {
+            return Err(Error::KeyIsEmpty);
+        }
+        Ok(Self::new(secret))
+    }
+
+    fn key(&self, salt: &Salt) -> Result<Key, Error> {
+        let mut keys = self
+            .keys
+            .lock()
+            .map_err(|e| Error::KeyDerivationFailed(e.to_string()))?;
+        if let Some(key) = keys.get(salt) {
+            return Ok(*key);
+        }
+        let mut key = Key::default();
+        Argon2::default()
+            .hash_password_into(&self.secret, salt, &mut key)
+            .map_err(|e| Error::KeyDerivationFailed(e.to_string()))?;
+        keys.insert(*salt, key);
+        Ok(key)
+    }
+
+    /// Encrypts the content into a json envelope, so it still is a valid json file.
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+        let cipher = XChaCha20Poly1305::new(&self.key(&self.salt)?);
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|e| Error::EncryptionFailed(e.to_string()))?;
+        let envelope = Envelope {
+            encrypted: Sealed {
+                version: ENVELOPE_VERSION,
+                salt: to_hex(&self.salt),
+                nonce: to_hex(&nonce),
+                ciphertext: to_hex(&ciphertext),
+            },
+        };
+        Ok(serde_json::to_string_pretty(&envelope)
+            .map_err(|e| Error::EncryptionFailed(e.to_string()))?
+            .into_bytes())
+    }
+
+    /// Decrypts a sealed file, content that is not sealed is given back as it is.
+    pub fn open(&self, content: &str) -> Result<String, Error> {
+        String::from_utf8(self.open_bytes(content.as_bytes())?)
+            .map_err(|e| Error::DecryptionFailed(e.to_string()))
+    }
+
+    /// Like [`Self::open`], for files that are not text, like sealed attachments.
+    pub fn open_bytes(&self, content: &[u8]) -> Result<Vec<u8>, Error> {
+        let Ok(envelope) = serde_json::from_slice::<Envelope>(content) else {
+            return Ok(content.to_vec());
+        };
+        let sealed = envelope.encrypted;
+        if sealed.version != ENVELOPE_VERSION {
+            return Err(Error::DecryptionFailed(format!(
+                "envelope version {} is not supported",
+                sealed.version
+            )));
+        }
+        let salt: Salt = from_hex(&sealed.salt)?
+            .try_into()
+            .map_err(|_e| Error::DecryptionFailed("salt has the wrong length".to_owned()))?;
+        let nonce = from_hex(&sealed.nonce)?;
+        if nonce.len() != XNonce::default().len() {
+            return Err(Error::DecryptionFailed(
+                "nonce has the wrong length".to_owned(),
+            ));
+        }
+        XChaCha20Poly1305::new(&self.key(&salt)?)
+            .decrypt(
+                XNonce::from_slice(&nonce),
+                from_hex(&sealed.ciphertext)?.as_slice(),
+            )
+            .map_err(|_e| Error::DecryptionFailed("wrong key or corrupted file".to_owned()))
+    }
+}
+
+/// Lets the migrations read and write sealed stores.
+impl migration::Codec for Keyring {
+    fn decode(&self, content: String) -> Result<String, migration::Error> {
+        self.open(&content)
+            .map_err(|e| migration::Error::CodecFailed(e.to_string()))
+    }
+    fn encode(&self, content: String) -> Result<Vec<u8>, migration::Error> {
+        self.seal(content.as_bytes())
+            .map_err(|e| migration::Error::CodecFailed(e.to_string()))
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>, Error> {
+    if !hex.len().is_multiple_of(2) {
+        return Err(Error::DecryptionFailed("hex has an odd length".to_owned()));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+                .ok_or_else(|| Error::DecryptionFailed("invalid hex".to_owned()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod testing {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use rstest::*;
+
+    const fn is_normal<T: Sized + Send + Sync + Unpin>() {}
+
+    #[test]
+    const fn normal_types() {
+        is_normal::<Keyring>();
+    }
+
+    #[rstest]
+    fn round_trip() {
+        let keyring = Keyring::new(b"correct horse".to_vec());
+        let sealed = keyring.seal(b"{\"body\": \"secret\"}").unwrap();
+        let sealed = String::from_utf8(sealed).unwrap();
+        assert!(!sealed.contains("secret"));
+        assert_eq!(keyring.open(&sealed).unwrap(), "{\"body\": \"secret\"}");
+
+        // a new session with the same secret has a new salt, but can still read the old files
+        let later = Keyring::new(b"correct horse".to_vec());
+        assert_eq!(later.open(&sealed).unwrap(), "{\"body\": \"secret\"}");
+    }
+
+    #[rstest]
+    fn bytes_round_trip() {
+        let keyring = Keyring::new(b"correct horse".to_vec());
+        // not valid utf-8, like most photos
+        let pixels = vec![0xff_u8, 0xd8, 0xff, 0xe0, 0x00, 0x80];
+        let sealed = keyring.seal(&pixels).unwrap();
+        assert_ne!(sealed, pixels);
+        assert_eq!(keyring.open_bytes(&sealed).unwrap(), pixels);
+        assert_eq!(keyring.open_bytes(&pixels).unwrap(), pixels);
+    }
+
+    #[rstest]
+    fn wrong_secret() {
+        let sealed = Keyring::new(b"correct horse".to_vec()).seal(b"{}").unwrap();
+        let other = Keyring::new(b"battery staple".to_vec());
+        assert!(other.open(&String::from_utf8(sealed).unwrap()).is_err());
+    }
+
+    #[rstest]
+    fn plaintext_passes_through() {
+        let keyring = Keyring::new(b"correct horse".to_vec());
+        assert_eq!(keyring.open("{\"body\": 1}").unwrap(), "{\"body\": 1}");
+    }
+
+    #[rstest]
+    #[case::empty("", vec![])]
+    #[case::bytes("00ff10", vec![0, 255, 16])]
+    fn hex_round_trip(#[case] hex: &str, #[case] bytes: Vec<u8>) {
+        assert_eq!(from_hex(hex).unwrap(), bytes);
+        assert_eq!(to_hex(&bytes), hex);
+    }
+}
@@ -1,44 +1,118 @@
-use std::{fmt::Display, path::PathBuf, sync::Arc};
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use clap::ValueEnum;
 use color_eyre::owo_colors::OwoColorize;
-use time::{formatting::Formattable, OffsetDateTime};
+use time::{format_description::well_known::Rfc3339, formatting::Formattable, OffsetDateTime};
 
+use crate::context::Context;
 use crate::files::ToFileName;
+use crate::output::Row;
 
-use super::Error;
+use super::{migration, Attachment, Error, Keyring};
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Entry {
+    pub schema_version: u32,
     #[serde(with = "time::serde::rfc3339")]
     pub at: OffsetDateTime,
     pub body: Arc<String>,
     pub tag: Vec<String>,
     pub mood: Mood,
     pub people: Vec<String>,
+    /// Entries written before attachments existed have none
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    /// Where the entry was written, `None` when nothing was captured
+    #[serde(default)]
+    pub context: Option<Context>,
 }
 
+/// How the moment of the entry felt.
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Default, ValueEnum)]
-pub enum Mood {
-    Good,
-    Bad,
-    #[default]
-    Neutral,
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Mood {
+    /// From [`Mood::WORST`] to [`Mood::BEST`], zero being neutral
+    pub score: i8,
+    /// From zero to [`Mood::MAX_ENERGY`]
+    pub energy: Option<u8>,
+    pub emotions: Vec<String>,
+}
+
+impl Mood {
+    pub const WORST: i8 = -5;
+    pub const BEST: i8 = 5;
+    pub const MAX_ENERGY: u8 = 10;
+    /// The scores that the old `Good`, `Bad` and `Neutral` moods map to.
+    pub const GOOD: i8 = 3;
+    pub const BAD: i8 = -3;
+    pub const NEUTRAL: i8 = 0;
+    /// The scores from which on a mood counts as good, or down to which it counts as bad, so the
+    /// old `Good` and `Bad` moods are well inside them.
+    pub const GOOD_FROM: i8 = 2;
+    pub const BAD_FROM: i8 = -2;
+
+    #[must_use]
+    pub const fn from_score(score: i8) -> Self {
+        Self {
+            score,
+            energy: None,
+            emotions: vec![],
+        }
+    }
+
+    /// Accepts a number between [`Mood::WORST`] and [`Mood::BEST`], or one of `good`, `bad` and
+    /// `neutral`.
+    pub fn parse_score(input: &str) -> Result<i8, Error> {
+        let score = match input.trim().to_lowercase().as_str() {
+            "good" => Self::GOOD,
+            "bad" => Self::BAD,
+            "neutral" => Self::NEUTRAL,
+            other => other
+                .parse()
+                .map_err(|_e| Error::UnknownMood(input.to_owned()))?,
+        };
+        if !(Self::WORST..=Self::BEST).contains(&score) {
+            return Err(Error::MoodScoreIsOutOfRange(score));
+        }
+        Ok(score)
+    }
+
+    pub fn parse_energy(input: &str) -> Result<u8, Error> {
+        let energy = input
+            .trim()
+            .parse()
+            .map_err(|_e| Error::UnknownEnergy(input.to_owned()))?;
+        if energy > Self::MAX_ENERGY {
+            return Err(Error::EnergyIsOutOfRange(energy));
+        }
+        Ok(energy)
+    }
+
+    #[must_use]
+    pub const fn is_good(&self) -> bool {
+        self.score >= Self::GOOD_FROM
+    }
+
+    #[must_use]
+    pub const fn is_bad(&self) -> bool {
+        self.score <= Self::BAD_FROM
+    }
 }
 
 impl Display for Mood {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Self::Good => "Good",
-                Self::Bad => "Bad",
-                Self::Neutral => "Neutral",
-            }
-        )
+        write!(f, "{:+}", self.score)?;
+        if let Some(energy) = self.energy {
+            write!(f, " ⚡{energy}")?;
+        }
+        if !self.emotions.is_empty() {
+            write!(f, " {}", self.emotions.join(", "))?;
+        }
+        Ok(())
     }
 }
 
@@ -69,11 +143,38 @@ impl TryFrom<PathBuf> for Entry {
     type Error = Error;
 
     fn try_from(value: PathBuf) -> Result<Self, Self::Error> {
-        let content = fs_extra::file::read_to_string(value).map_err(Error::FileCouldNotBeRead)?;
+        Self::read(&value, None)
+    }
+}
 
-        let entry: Self = serde_json::from_str(&content)
-            .map_err(|e| Error::FileCouldNotDeserializeEntryFromJson(e, content))?;
-        Ok(entry)
+impl Row for Entry {
+    fn columns() -> Vec<&'static str> {
+        vec![
+            "at",
+            "mood",
+            "energy",
+            "emotions",
+            "tags",
+            "people",
+            "attachments",
+            "body",
+        ]
+    }
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.at.format(&Rfc3339).unwrap_or_default(),
+            self.mood.score.to_string(),
+            self.mood.energy.map(|e| e.to_string()).unwrap_or_default(),
+            self.mood.emotions.join(" "),
+            self.tag.join(" "),
+            self.people.join(" "),
+            self.attachments
+                .iter()
+                .map(|a| a.name.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+            self.body.to_string(),
+        ]
     }
 }
 
@@ -87,6 +188,27 @@ impl ToFileName for OffsetDateTime {
     }
 }
 impl Entry {
+    /// Reads an entry file, decrypting it if it was sealed.
+    pub fn read(path: &Path, keyring: Option<&Keyring>) -> Result<Self, Error> {
+        let content = fs_extra::file::read_to_string(path).map_err(Error::FileCouldNotBeRead)?;
+        let content = match keyring {
+            Some(keyring) => keyring.open(&content)?,
+            None => content,
+        };
+
+        migration::REGISTRY
+            .read(&content)
+            .map_err(|e| Error::CouldNotMigrateEntry(e, path.display().to_string()))
+    }
+    /// The content of the entry file, sealed if there is a keyring.
+    pub fn to_file_content(&self, keyring: Option<&Keyring>) -> Result<Vec<u8>, Error> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| Error::FileCouldNotSerializeEntryIntoJson(e, self.at.to_string()))?;
+        match keyring {
+            Some(keyring) => keyring.seal(content.as_bytes()),
+            None => Ok(content.into_bytes()),
+        }
+    }
     pub fn pretty_formated(
         &self,
         time_format_descriptor_for_display: &(impl Formattable + ?Sized),
@@ -108,7 +230,21 @@ impl Entry {
             })
         };
 
-        Ok(format!("{date}\n{body}\n{tags}"))
+        let mood = format!("{}", self.mood.dimmed());
+
+        let attachments = self.attachments.iter().fold(String::new(), |accu, item| {
+            format!("{accu}\n📎 {}", item.blue())
+        });
+
+        let context = self
+            .context
+            .as_ref()
+            .map(|c| format!("\n{}", c.dimmed()))
+            .unwrap_or_default();
+
+        Ok(format!(
+            "{date} {mood}\n{body}\n{tags}{attachments}{context}"
+        ))
     }
 }
 
@@ -123,4 +259,39 @@ mod testing {
     const fn normal_types() {
         is_normal::<Entry>();
     }
+
+    #[rstest::rstest]
+    #[case::good("good", Ok(Mood::GOOD))]
+    #[case::bad("Bad", Ok(Mood::BAD))]
+    #[case::number("-4", Ok(-4))]
+    #[case::plus("+5", Ok(5))]
+    #[case::too_high("6", Err(()))]
+    #[case::word("ecstatic", Err(()))]
+    fn parse_score(#[case] input: &str, #[case] expected: Result<i8, ()>) {
+        assert_eq!(Mood::parse_score(input).map_err(|_e| ()), expected);
+    }
+
+    #[rstest::rstest]
+    #[case::good(Mood::GOOD, true, false)]
+    #[case::bad(Mood::BAD, false, true)]
+    #[case::neutral(Mood::NEUTRAL, false, false)]
+    #[case::good_from(Mood::GOOD_FROM, true, false)]
+    #[case::bad_from(Mood::BAD_FROM, false, true)]
+    fn good_and_bad(#[case] score: i8, #[case] good: bool, #[case] bad: bool) {
+        let mood = Mood::from_score(score);
+        assert_eq!((mood.is_good(), mood.is_bad()), (good, bad));
+    }
+
+    #[rstest::rstest]
+    fn old_entries_and_the_index() {
+        let old = r#"{"schema_version":2,"at":"2023-10-24T18:46:00Z","body":"body","tag":[],
+            "mood":{"score":0,"energy":null,"emotions":[]},"people":[]}"#;
+        let entry: Option<Entry> = migration::REGISTRY.read(old).ok();
+        assert!(entry.as_ref().is_some_and(|e| e.context.is_none()));
+        let cached: Option<Entry> = bincode::serialize(&entry)
+            .ok()
+            .and_then(|c| bincode::deserialize(&c).ok())
+            .flatten();
+        assert_eq!(cached, entry);
+    }
 }
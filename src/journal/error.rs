@@ -1,4 +1,4 @@
-use crate::git;
+use crate::{git, migration, output};
 
 pub(super) type Result<T> = std::result::Result<T, Error>;
 
@@ -40,6 +40,42 @@ pub enum Error {
     FileNameHasInvalidCharacters,
     #[error("the path is not a file")]
     IsNotAFile,
+    #[error("could not parse the frontmatter: {0}")]
+    FrontmatterCouldNotBeParsed(String),
+    #[error("mood is not known: {0}")]
+    UnknownMood(String),
+    #[error("mood score {0} is out of range")]
+    MoodScoreIsOutOfRange(i8),
+    #[error("energy is not known: {0}")]
+    UnknownEnergy(String),
+    #[error("energy {0} is out of range")]
+    EnergyIsOutOfRange(u8),
+    #[error("could not read the key file: {0}")]
+    KeyFileCouldNotBeRead(std::io::Error),
+    #[error("the encryption key is empty")]
+    KeyIsEmpty,
+    #[error("could not derive the encryption key: {0}")]
+    KeyDerivationFailed(String),
+    #[error("could not encrypt the entry: {0}")]
+    EncryptionFailed(String),
+    #[error("could not decrypt the entry: {0}")]
+    DecryptionFailed(String),
+    #[error("could not read the attachment: {0}")]
+    AttachmentCouldNotBeRead(std::io::Error),
+    #[error("{0} entries match, be more specific")]
+    EntryIsAmbiguous(usize),
+    #[error("could not read entry {1}: {0}")]
+    CouldNotMigrateEntry(migration::Error, String),
+    #[error("migrating the journal failed: {0}")]
+    MigrationFailed(#[from] migration::Error),
+    #[error("the entry has no body, nothing to save")]
+    EntryBodyIsEmpty,
+    #[error("could not parse the imported content: {0}")]
+    ImportCouldNotBeParsed(String),
+    #[error("could not parse the day one export: {0}")]
+    DayOneExportCouldNotBeParsed(serde_json::Error),
+    #[error("could not write the output: {0}")]
+    OutputFailed(#[from] output::Error),
 }
 #[cfg(test)]
 mod testing {
@@ -0,0 +1,255 @@
+use nom::{
+    bytes::complete::{tag, take_while1},
+    character::complete::{char, line_ending, not_line_ending, space0},
+    combinator::{opt, rest},
+    error::{context, ContextError, ParseError, VerboseError},
+    multi::many0,
+    sequence::{delimited, preceded, separated_pair, terminated, tuple},
+    Finish,
+};
+
+use std::sync::Arc;
+
+use time::OffsetDateTime;
+use tracing::warn;
+
+use super::{migration, Entry, Error, Mood};
+
+/// The metadata block at the top of a markdown like document, followed by its body.
+///
+/// ```text
+/// ---
+/// tags: work, retro
+/// mood: 3
+/// energy: 7
+/// emotions: calm, hopeful
+/// people: alice
+/// ---
+/// the body
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Frontmatter {
+    pub tags: Vec<String>,
+    /// See [`Mood::parse_score`] for the accepted values
+    pub mood: Option<i8>,
+    pub energy: Option<u8>,
+    pub emotions: Vec<String>,
+    pub people: Vec<String>,
+    pub body: String,
+}
+
+impl Frontmatter {
+    /// Parses a document, a document without the metadata block is all body. Fields this does not
+    /// know about are left out, and lines of the block that start with `#` are comments.
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        let (fields, body) = fields(input).map_err(Error::FrontmatterCouldNotBeParsed)?;
+
+        fields.into_iter().try_fold(
+            Self {
+                body: body.trim().to_owned(),
+                ..Self::default()
+            },
+            |mut fm, (key, value)| {
+                match key.trim().to_lowercase().as_str() {
+                    "tags" | "tag" => fm.tags = list_value(value),
+                    "people" | "person" => fm.people = list_value(value),
+                    "mood" => {
+                        fm.mood = match value.trim() {
+                            "" => None,
+                            v => Some(Mood::parse_score(v)?),
+                        }
+                    }
+                    "energy" => {
+                        fm.energy = match value.trim() {
+                            "" => None,
+                            v => Some(Mood::parse_energy(v)?),
+                        }
+                    }
+                    "emotions" | "emotion" => fm.emotions = list_value(value),
+                    // like the title of a document written elsewhere
+                    other => warn!("ignoring the unknown frontmatter field {other}"),
+                };
+                Ok(fm)
+            },
+        )
+    }
+
+    /// The inverse of [`Frontmatter::parse`], every field is written out so it can be filled in,
+    /// with a comment on what a mood left empty means.
+    #[must_use]
+    pub fn render(&self) -> String {
+        format!(
+            "---\ntags: {}\n# from {} to {}, or good, bad or neutral, it is neutral when left empty\nmood: {}\nenergy: {}\nemotions: {}\npeople: {}\n---\n{}\n",
+            self.tags.join(", "),
+            Mood::WORST,
+            Mood::BEST,
+            self.mood.map(|m| m.to_string()).unwrap_or_default(),
+            self.energy.map(|e| e.to_string()).unwrap_or_default(),
+            self.emotions.join(", "),
+            self.people.join(", "),
+            self.body,
+        )
+    }
+
+    /// Turns a filled in document into an entry written at the given moment.
+    pub fn into_entry(self, at: OffsetDateTime) -> Result<Entry, Error> {
+        if self.body.trim().is_empty() {
+            return Err(Error::EntryBodyIsEmpty);
+        }
+        Ok(Entry {
+            schema_version: migration::CURRENT_VERSION,
+            at,
+            body: Arc::new(self.body),
+            tag: self.tags,
+            mood: Mood {
+                score: self.mood.unwrap_or_default(),
+                energy: self.energy,
+                emotions: self.emotions,
+            },
+            people: self.people,
+            attachments: vec![],
+            context: None,
+        })
+    }
+}
+
+/// The `key: value` fields of the metadata block and the body after it, the error says where the
+/// document could not be parsed.
+pub(crate) fn fields(input: &str) -> Result<(Fields<'_>, &str), String> {
+    let (_, parsed) = document_parser::<VerboseError<&str>>(input)
+        .finish()
+        .map_err(|e| nom::error::convert_error(input, e))?;
+    Ok(parsed)
+}
+
+/// Accepts both `a, b` and `[a, b]`.
+pub(crate) fn list_value(value: &str) -> Vec<String> {
+    value
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+pub(crate) type Fields<'a> = Vec<(&'a str, &'a str)>;
+
+fn document_parser<'a, E>(input: &'a str) -> Result<(&'a str, (Fields<'a>, &'a str)), nom::Err<E>>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+{
+    tuple((
+        context(
+            "frontmatter block",
+            opt(delimited(
+                tuple((tag("---"), space0, line_ending)),
+                terminated(
+                    many0(preceded(many0(comment_parser), field_parser)),
+                    many0(comment_parser),
+                ),
+                tuple((tag("---"), space0, opt(line_ending))),
+            )),
+        ),
+        context("body", rest),
+    ))(input)
+    .map(|(i, (fields, body))| (i, (fields.unwrap_or_default(), body)))
+}
+
+fn comment_parser<'a, E>(input: &'a str) -> Result<(&'a str, &'a str), nom::Err<E>>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+{
+    context(
+        "comment",
+        delimited(char('#'), not_line_ending, line_ending),
+    )(input)
+}
+
+fn field_parser<'a, E>(input: &'a str) -> Result<(&'a str, (&'a str, &'a str)), nom::Err<E>>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+{
+    context(
+        "field",
+        terminated(
+            separated_pair(
+                take_while1(|c: char| c.is_alphanumeric() || c == '_'),
+                tuple((char(':'), space0)),
+                not_line_ending,
+            ),
+            line_ending,
+        ),
+    )(input)
+}
+
+#[cfg(test)]
+mod testing {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case::full(
+        "---\ntags: work, retro\nmood: good\nenergy: 7\nemotions: calm\npeople: [alice, bob]\n---\nthe body\n",
+        Frontmatter {
+            tags: vec!["work".to_owned(), "retro".to_owned()],
+            mood: Some(Mood::GOOD),
+            energy: Some(7),
+            emotions: vec!["calm".to_owned()],
+            people: vec!["alice".to_owned(), "bob".to_owned()],
+            body: "the body".to_owned(),
+        }
+    )]
+    #[case::empty_fields(
+        "---\ntags:\nmood:\n---\nbody",
+        Frontmatter { body: "body".to_owned(), ..Frontmatter::default() }
+    )]
+    #[case::unknown_field(
+        "---\ntitle: Retro\ntags: work\n---\nbody",
+        Frontmatter { tags: vec!["work".to_owned()], body: "body".to_owned(), ..Frontmatter::default() }
+    )]
+    #[case::comments(
+        "---\n# what the mood means\nmood: good\n# and the end\n---\nbody",
+        Frontmatter { mood: Some(Mood::GOOD), body: "body".to_owned(), ..Frontmatter::default() }
+    )]
+    #[case::no_frontmatter(
+        "just a body",
+        Frontmatter { body: "just a body".to_owned(), ..Frontmatter::default() }
+    )]
+    fn parse_happy(#[case] input: &str, #[case] expected: Frontmatter) {
+        assert_eq!(Frontmatter::parse(input).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case::unknown_mood("---\nmood: ecstatic\n---\nbody")]
+    #[case::too_much_energy("---\nenergy: 11\n---\nbody")]
+    fn parse_unhappy(#[case] input: &str) {
+        assert!(Frontmatter::parse(input).is_err());
+    }
+
+    #[rstest]
+    #[case::full(Frontmatter {
+        tags: vec!["daily".to_owned()],
+        mood: Some(-2),
+        energy: Some(3),
+        emotions: vec!["tired".to_owned(), "content".to_owned()],
+        people: vec!["alice".to_owned(), "bob".to_owned()],
+        body: "what went well?".to_owned(),
+    })]
+    #[case::empty(Frontmatter::default())]
+    fn render_then_parse(#[case] fm: Frontmatter) {
+        assert_eq!(Frontmatter::parse(&fm.render()).unwrap(), fm);
+    }
+
+    #[rstest]
+    fn empty_body_is_not_an_entry() {
+        let fm = Frontmatter::parse("---\ntags: daily\n---\n\n").unwrap();
+        assert!(fm.into_entry(OffsetDateTime::now_utc()).is_err());
+    }
+}
@@ -0,0 +1,332 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use clap::ValueEnum;
+use nom::{
+    branch::alt,
+    bytes::complete::tag_no_case,
+    character::complete::{char, space0, space1},
+    combinator::{all_consuming, map, opt, rest},
+    error::{context, ContextError, ParseError},
+    sequence::{delimited, preceded, tuple},
+    Finish,
+};
+use time::UtcOffset;
+use time::{format_description::well_known, Date, Month, OffsetDateTime, PrimitiveDateTime, Time};
+
+use crate::fold_or_err;
+
+use super::{migration, Entry, Error, Frontmatter, Mood};
+
+/// The journaling tools that entries can be imported from.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ValueEnum)]
+pub enum Format {
+    /// jrnl's plain-text export
+    Jrnl,
+    /// Day One's JSON export
+    DayOne,
+    /// A directory of markdown files named after their date, like `2023-10-24.md`
+    Markdown,
+}
+
+pub fn read_entries(format: Format, path: &Path, offset: UtcOffset) -> Result<Vec<Entry>, Error> {
+    match format {
+        Format::Jrnl => from_jrnl(&read(path)?, offset),
+        Format::DayOne => from_day_one(&read(path)?, offset),
+        Format::Markdown => markdown_files(path)?
+            .iter()
+            .map(|p| {
+                let file_name = p
+                    .file_name()
+                    .ok_or(Error::IsNotAFile)?
+                    .to_str()
+                    .ok_or(Error::FileNameHasInvalidCharacters)?;
+                from_markdown(file_name, &read(p)?, offset)
+            })
+            .try_fold(vec![], fold_or_err)
+            .map(apart),
+    }
+}
+
+/// Moves entries that are at the same moment as the one before them a second later, so markdown
+/// files of the same day stay different entries. The files are sorted by name, so importing them
+/// again gives the same moments.
+fn apart(mut entries: Vec<Entry>) -> Vec<Entry> {
+    let mut before: Option<OffsetDateTime> = None;
+    for entry in &mut entries {
+        if let Some(at) = before.filter(|at| entry.at <= *at && entry.at.date() == at.date()) {
+            entry.at = at + time::Duration::SECOND;
+        }
+        before = Some(entry.at);
+    }
+    entries
+}
+
+fn read(path: &Path) -> Result<String, Error> {
+    fs_extra::file::read_to_string(path).map_err(Error::FileCouldNotBeRead)
+}
+
+fn markdown_files(path: &Path) -> Result<Vec<PathBuf>, Error> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files: Vec<PathBuf> = fs_extra::dir::get_dir_content(path)
+        .map_err(Error::DirCouldNotBeRead)?
+        .files
+        .into_iter()
+        .map(PathBuf::from)
+        .filter(|p| p.extension().is_some_and(|x| x == "md"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Parses jrnl's plain-text format, where every entry starts with a line like
+/// `[2023-10-24 18:46] The title.` and `@tag`s are sprinkled through the text.
+pub fn from_jrnl(content: &str, offset: UtcOffset) -> Result<Vec<Entry>, Error> {
+    let mut entries: Vec<(OffsetDateTime, Vec<String>)> = vec![];
+    for line in content.lines() {
+        match jrnl_header_parser::<nom::error::Error<&str>>(line).finish() {
+            Ok((_, (at, title))) => {
+                entries.push((at.assume_offset(offset), vec![title.trim().to_owned()]))
+            }
+            Err(_) => entries
+                .last_mut()
+                .ok_or_else(|| {
+                    Error::ImportCouldNotBeParsed(format!("text before the first entry: {line}"))
+                })?
+                .1
+                .push(line.to_owned()),
+        }
+    }
+
+    Ok(entries
+        .into_iter()
+        .map(|(at, lines)| {
+            let body = lines.join("\n").trim().to_owned();
+            Entry {
+                schema_version: migration::CURRENT_VERSION,
+                at,
+                tag: jrnl_tags(&body),
+                body: Arc::new(body),
+                mood: Mood::default(),
+                people: vec![],
+                attachments: vec![],
+                context: None,
+            }
+        })
+        .collect())
+}
+
+fn jrnl_tags(body: &str) -> Vec<String> {
+    let mut tags: Vec<String> = body
+        .split_whitespace()
+        .filter_map(|w| w.strip_prefix('@'))
+        .map(|w| w.trim_end_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|w| !w.is_empty())
+        .map(ToOwned::to_owned)
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+fn jrnl_header_parser<'a, E>(
+    input: &'a str,
+) -> Result<(&'a str, (PrimitiveDateTime, &'a str)), nom::Err<E>>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+{
+    all_consuming(tuple((
+        alt((
+            delimited(char('['), jrnl_date_time_parser, char(']')),
+            jrnl_date_time_parser,
+        )),
+        preceded(space0, rest),
+    )))(input)
+}
+
+fn jrnl_date_time_parser<'a, E>(input: &'a str) -> Result<(&'a str, PrimitiveDateTime), nom::Err<E>>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+{
+    let (remaining, (year, _, month, _, day, _, hour, _, minute, second, meridiem)) = tuple((
+        context("year", nom::character::complete::i32),
+        char('-'),
+        context("month", nom::character::complete::u8),
+        char('-'),
+        context("day", nom::character::complete::u8),
+        space1,
+        context("hour", nom::character::complete::u8),
+        char(':'),
+        context("minute", nom::character::complete::u8),
+        opt(preceded(char(':'), nom::character::complete::u8)),
+        opt(preceded(
+            space1,
+            alt((
+                map(tag_no_case("am"), |_| false),
+                map(tag_no_case("pm"), |_| true),
+            )),
+        )),
+    ))(input)?;
+
+    let hour = match meridiem {
+        Some(true) if hour < 12 => hour + 12,
+        Some(false) if hour == 12 => 0,
+        _ => hour,
+    };
+    let failure = || nom::Err::Failure(E::from_error_kind(input, nom::error::ErrorKind::Verify));
+    let date = Month::try_from(month)
+        .ok()
+        .and_then(|m| Date::from_calendar_date(year, m, day).ok())
+        .ok_or_else(failure)?;
+    let time = Time::from_hms(hour, minute, second.unwrap_or_default())
+        .ok()
+        .ok_or_else(failure)?;
+
+    Ok((remaining, PrimitiveDateTime::new(date, time)))
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[serde(rename_all = "camelCase")]
+struct DayOneExport {
+    entries: Vec<DayOneEntry>,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[serde(rename_all = "camelCase")]
+struct DayOneEntry {
+    #[serde(with = "time::serde::rfc3339")]
+    creation_date: OffsetDateTime,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// Parses the `Journal.json` of a Day One export. Day One keeps no mood or people of its own, so
+/// those are left empty and only the date, the text and the tags are carried over.
+pub fn from_day_one(content: &str, offset: UtcOffset) -> Result<Vec<Entry>, Error> {
+    let export: DayOneExport =
+        serde_json::from_str(content).map_err(Error::DayOneExportCouldNotBeParsed)?;
+    Ok(export
+        .entries
+        .into_iter()
+        .map(|e| Entry {
+            schema_version: migration::CURRENT_VERSION,
+            at: e.creation_date.to_offset(offset),
+            body: Arc::new(e.text.trim().to_owned()),
+            tag: e.tags,
+            mood: Mood::default(),
+            people: vec![],
+            attachments: vec![],
+            context: None,
+        })
+        .collect())
+}
+
+/// Parses a markdown file named after its date (`2023-10-24.md` or `2023-10-24-anything.md`),
+/// with an optional [`Frontmatter`] block for tags, mood and people.
+pub fn from_markdown(file_name: &str, content: &str, offset: UtcOffset) -> Result<Entry, Error> {
+    let date = file_name
+        .get(..10)
+        .and_then(|d| Date::parse(d, &well_known::Iso8601::DATE).ok())
+        .ok_or_else(|| {
+            Error::ImportCouldNotBeParsed(format!(
+                "file name does not start with a date: {file_name}"
+            ))
+        })?;
+    Frontmatter::parse(content)?.into_entry(date.midnight().assume_offset(offset))
+}
+
+#[cfg(test)]
+mod testing {
+    #![allow(clippy::unwrap_used, clippy::indexing_slicing)]
+
+    use super::*;
+    use rstest::*;
+
+    fn at(y: i32, m: Month, d: u8, h: u8, min: u8, s: u8) -> OffsetDateTime {
+        Date::from_calendar_date(y, m, d)
+            .unwrap()
+            .with_hms(h, min, s)
+            .unwrap()
+            .assume_utc()
+    }
+
+    #[rstest]
+    fn jrnl_happy() {
+        let input = "[2023-10-24 18:46] Finished the migration. @work\nit went well\n\n2023-10-25 09:05:10 PM Another @one @work.\n";
+        let entries = from_jrnl(input, UtcOffset::UTC).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].at, at(2023, Month::October, 24, 18, 46, 0));
+        assert_eq!(
+            entries[0].body.as_str(),
+            "Finished the migration. @work\nit went well"
+        );
+        assert_eq!(entries[0].tag, vec!["work".to_owned()]);
+        assert_eq!(entries[1].at, at(2023, Month::October, 25, 21, 5, 10));
+        assert_eq!(entries[1].tag, vec!["one".to_owned(), "work".to_owned()]);
+    }
+
+    #[rstest]
+    #[case::text_before_entries("some text\n[2023-10-24 18:46] title")]
+    fn jrnl_unhappy(#[case] input: &str) {
+        assert!(from_jrnl(input, UtcOffset::UTC).is_err());
+    }
+
+    #[rstest]
+    fn day_one_happy() {
+        let input = r#"{"metadata":{"version":"1.0"},"entries":[{"creationDate":"2023-10-24T18:46:00Z","text":"hello there","tags":["travel"],"uuid":"ABC"}]}"#;
+        let entries = from_day_one(input, UtcOffset::UTC).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].at, at(2023, Month::October, 24, 18, 46, 0));
+        assert_eq!(entries[0].body.as_str(), "hello there");
+        assert_eq!(entries[0].tag, vec!["travel".to_owned()]);
+    }
+
+    #[rstest]
+    fn markdown_happy() {
+        let entry = from_markdown(
+            "2023-10-24-retro.md",
+            "---\ntags: retro\nmood: bad\npeople: alice\n---\n# Retro\nit was rough",
+            UtcOffset::UTC,
+        )
+        .unwrap();
+        assert_eq!(entry.at, at(2023, Month::October, 24, 0, 0, 0));
+        assert_eq!(entry.body.as_str(), "# Retro\nit was rough");
+        assert_eq!(entry.mood, Mood::from_score(Mood::BAD));
+        assert_eq!(entry.people, vec!["alice".to_owned()]);
+    }
+
+    #[rstest]
+    fn markdown_of_the_same_day() {
+        let entries = apart(
+            [
+                "2023-10-24-daily.md",
+                "2023-10-24-retro.md",
+                "2023-10-25.md",
+            ]
+            .into_iter()
+            .map(|file_name| from_markdown(file_name, "body", UtcOffset::UTC).unwrap())
+            .collect(),
+        );
+        let moments: Vec<OffsetDateTime> = entries.iter().map(|e| e.at).collect();
+        assert_eq!(
+            moments,
+            [
+                at(2023, Month::October, 24, 0, 0, 0),
+                at(2023, Month::October, 24, 0, 0, 1),
+                at(2023, Month::October, 25, 0, 0, 0)
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::no_date("retro.md")]
+    #[case::bad_date("2023-13-24.md")]
+    fn markdown_unhappy(#[case] file_name: &str) {
+        assert!(from_markdown(file_name, "body", UtcOffset::UTC).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use serde_json::{json, Value};
+
+use crate::migration::{Error, Registry};
+
+use super::Mood;
+
+/// Every upgrade that entry documents went through.
+pub const REGISTRY: Registry = Registry {
+    kind: "entry",
+    steps: &[v1_to_v2],
+};
+
+/// The version of the entry documents that this build writes.
+pub const CURRENT_VERSION: u32 = REGISTRY.current_version();
+
+/// The mood went from one of `Good`, `Bad` and `Neutral` to a score with energy and emotions.
+fn v1_to_v2(mut document: Value) -> Result<Value, Error> {
+    let entry = document
+        .as_object_mut()
+        .ok_or(Error::DocumentIsNotAnObject(REGISTRY.kind))?;
+    let score = match entry.get("mood").and_then(Value::as_str) {
+        Some("Good") => Mood::GOOD,
+        Some("Bad") => Mood::BAD,
+        Some("Neutral") | None => Mood::NEUTRAL,
+        Some(other) => return Err(Error::StepFailed(format!("mood {other} is not known"))),
+    };
+    entry.insert(
+        "mood".to_owned(),
+        json!({ "score": score, "energy": null, "emotions": [] }),
+    );
+    Ok(document)
+}
+
+#[cfg(test)]
+mod testing {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::migration::version_of;
+    use rstest::*;
+
+    #[rstest]
+    #[case::good("Good", Mood::GOOD)]
+    #[case::bad("Bad", Mood::BAD)]
+    #[case::neutral("Neutral", Mood::NEUTRAL)]
+    fn v1_is_upgraded(#[case] mood: &str, #[case] score: i8) {
+        let old = json!({
+            "at": "2023-10-24T18:46:00Z",
+            "body": "body",
+            "tag": [],
+            "mood": mood,
+            "people": [],
+        });
+        let new = REGISTRY.migrate(old).unwrap();
+        assert_eq!(version_of(&new), CURRENT_VERSION);
+        assert_eq!(
+            new.get("mood").unwrap(),
+            &json!({ "score": score, "energy": null, "emotions": [] })
+        );
+    }
+
+    #[rstest]
+    fn current_is_untouched() {
+        let current = json!({
+            "schema_version": CURRENT_VERSION,
+            "mood": { "score": 1_i8, "energy": 4_u8, "emotions": ["calm"] },
+        });
+        assert_eq!(REGISTRY.migrate(current.clone()).unwrap(), current);
+    }
+}
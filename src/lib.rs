@@ -1,8 +1,14 @@
+pub mod completion;
+pub mod context;
 pub mod effects;
 pub mod files;
 pub mod git;
+pub mod index;
 pub mod input;
 pub mod journal;
+pub mod links;
+pub mod migration;
+pub mod output;
 pub mod river;
 pub mod tasks;
 pub mod timeutils;
@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while1},
+    combinator::map,
+    error::{context, ContextError, ParseError},
+    sequence::{delimited, preceded},
+    Finish,
+};
+
+use crate::journal::Book;
+
+/// A reference from a journal entry or a task to another item.
+///
+/// ```text
+/// finished the migration, see [[task:1697812345]] and [[entry:2023-10-24-18-46-00]]
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Link {
+    /// The id of the task
+    Task(i64),
+    /// The file name of the entry, without the `.json`
+    Entry(String),
+}
+
+/// Every link in the text, in the order they appear, text that only looks like a link is skipped.
+#[must_use]
+pub fn parse(text: &str) -> Vec<Link> {
+    let mut links = vec![];
+    let mut rest = text;
+    while let Some(start) = rest.find("[[") {
+        let candidate = rest.get(start..).unwrap_or_default();
+        match link_parser::<nom::error::Error<&str>>(candidate).finish() {
+            Ok((remaining, link)) => {
+                links.push(link);
+                rest = remaining;
+            }
+            Err(_) => rest = candidate.get(1..).unwrap_or_default(),
+        }
+    }
+    links
+}
+
+fn link_parser<'a, E>(input: &'a str) -> Result<(&'a str, Link), nom::Err<E>>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+{
+    delimited(
+        tag("[["),
+        alt((
+            context(
+                "task link",
+                map(
+                    preceded(tag("task:"), nom::character::complete::i64),
+                    Link::Task,
+                ),
+            ),
+            context(
+                "entry link",
+                map(
+                    preceded(
+                        tag("entry:"),
+                        take_while1(|c: char| c != ']' && !c.is_whitespace()),
+                    ),
+                    |name: &str| Link::Entry(name.to_owned()),
+                ),
+            ),
+        )),
+        tag("]]"),
+    )(input)
+}
+
+/// Which entries link to each task and to each other entry.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct Backlinks {
+    /// Task id to the file names of the entries that mention it
+    pub tasks: BTreeMap<i64, Vec<String>>,
+    /// Entry name to the file names of the entries that mention it
+    pub entries: BTreeMap<String, Vec<String>>,
+}
+
+impl Backlinks {
+    #[must_use]
+    pub fn to_task(&self, id: i64) -> &[String] {
+        self.tasks.get(&id).map_or(&[], Vec::as_slice)
+    }
+    #[must_use]
+    pub fn to_entry(&self, file_name: &str) -> &[String] {
+        self.entries
+            .get(file_name.strip_suffix(".json").unwrap_or(file_name))
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+impl From<&Book> for Backlinks {
+    fn from(book: &Book) -> Self {
+        let mut backlinks = Self::default();
+        for ent in book.entries.iter() {
+            for link in parse(&ent.entry.body) {
+                let linking = match link {
+                    Link::Task(id) => backlinks.tasks.entry(id).or_default(),
+                    Link::Entry(name) => backlinks.entries.entry(name).or_default(),
+                };
+                if !linking.contains(&ent.file_name) {
+                    linking.push(ent.file_name.clone());
+                }
+            }
+        }
+        backlinks
+    }
+}
+
+#[cfg(test)]
+mod testing {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::journal::{migration, Entry, EntryDescription, Mood};
+    use rstest::*;
+
+    const fn is_normal<T: Sized + Send + Sync + Unpin>() {}
+
+    #[test]
+    const fn normal_types() {
+        is_normal::<Link>();
+        is_normal::<Backlinks>();
+    }
+
+    #[rstest]
+    #[case::none("nothing to see here", vec![])]
+    #[case::task("see [[task:1697812345]].", vec![Link::Task(1_697_812_345)])]
+    #[case::entry(
+        "like [[entry:2023-10-24-18-46-00]] said",
+        vec![Link::Entry("2023-10-24-18-46-00".to_owned())]
+    )]
+    #[case::several(
+        "[[task:1]][[entry:a]] and [[task:2]]",
+        vec![Link::Task(1), Link::Entry("a".to_owned()), Link::Task(2)]
+    )]
+    #[case::not_links(
+        "[[task:abc]] [[note:1]] [[entry:]] [[[task:3]]",
+        vec![Link::Task(3)]
+    )]
+    fn parse_links(#[case] text: &str, #[case] expected: Vec<Link>) {
+        assert_eq!(parse(text), expected);
+    }
+
+    fn entry_description(file_name: &str, body: &str) -> EntryDescription {
+        EntryDescription {
+            entry: Entry {
+                schema_version: migration::CURRENT_VERSION,
+                at: time::OffsetDateTime::UNIX_EPOCH,
+                body: Arc::new(body.to_owned()),
+                tag: vec![],
+                mood: Mood::default(),
+                people: vec![],
+                attachments: vec![],
+                context: None,
+            },
+            file_name: file_name.to_owned(),
+        }
+    }
+
+    #[rstest]
+    fn backlinks() {
+        let book = Book::from((
+            vec![
+                entry_description("a.json", "started [[task:1]]"),
+                entry_description(
+                    "b.json",
+                    "finished [[task:1]], like [[entry:a]] said [[task:1]]",
+                ),
+            ],
+            PathBuf::new(),
+        ));
+        let backlinks = Backlinks::from(&book);
+        assert_eq!(backlinks.to_task(1), ["a.json", "b.json"]);
+        assert!(backlinks.to_task(2).is_empty());
+        assert_eq!(backlinks.to_entry("a.json"), ["b.json"]);
+    }
+}
@@ -0,0 +1,239 @@
+use std::path::PathBuf;
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+
+use crate::effects::{EffectKind, EffectMachine, FileWriterOpts};
+use crate::git;
+
+/// Upgrades a document from one version to the very next one.
+pub type Step = fn(Value) -> Result<Value, Error>;
+
+/// Every upgrade that a kind of stored document has gone through, `steps[0]` upgrades version 1 to
+/// version 2, `steps[1]` version 2 to version 3 and so on.
+#[derive(Clone, Copy, Debug)]
+pub struct Registry {
+    pub kind: &'static str,
+    pub steps: &'static [Step],
+}
+
+impl Registry {
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)] // there will never be 2^32 migrations
+    pub const fn current_version(&self) -> u32 {
+        self.steps.len() as u32 + 1
+    }
+
+    #[must_use]
+    pub fn is_outdated(&self, document: &Value) -> bool {
+        version_of(document) < self.current_version()
+    }
+
+    /// Runs every step between the version of the document and the current one.
+    pub fn migrate(&self, mut document: Value) -> Result<Value, Error> {
+        let from = version_of(&document);
+        if from > self.current_version() {
+            return Err(Error::UnsupportedSchemaVersion(self.kind, from));
+        }
+        let from = from.max(1);
+        for (version, step) in (from..).zip(self.steps.iter().skip(from as usize - 1)) {
+            document = step(document)?;
+            document
+                .as_object_mut()
+                .ok_or(Error::DocumentIsNotAnObject(self.kind))?
+                .insert("schema_version".to_owned(), json!(version + 1));
+        }
+        Ok(document)
+    }
+
+    /// Parses a document, upgrading it on the way if it is outdated.
+    pub fn read<T: DeserializeOwned>(&self, content: &str) -> Result<T, Error> {
+        let raw: Value = serde_json::from_str(content).map_err(Error::CouldNotParseDocument)?;
+        serde_json::from_value(self.migrate(raw)?).map_err(Error::CouldNotParseDocument)
+    }
+}
+
+/// How the documents of a store are stored on disk, for stores that are not plain json.
+pub trait Codec {
+    fn decode(&self, content: String) -> Result<String, Error>;
+    fn encode(&self, content: String) -> Result<Vec<u8>, Error>;
+}
+
+/// Documents stored as they are.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Plain;
+
+impl Codec for Plain {
+    fn decode(&self, content: String) -> Result<String, Error> {
+        Ok(content)
+    }
+    fn encode(&self, content: String) -> Result<Vec<u8>, Error> {
+        Ok(content.into_bytes())
+    }
+}
+
+/// Documents written before versioning was introduced have no version field, and are version 1.
+#[must_use]
+pub fn version_of(document: &Value) -> u32 {
+    document
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .map_or(1, |v| u32::try_from(v).unwrap_or(u32::MAX))
+}
+
+/// Rewrites every outdated document among the files of the store, in a single commit.
+pub fn rewrite_outdated<T>(
+    registry: &Registry,
+    files: Vec<PathBuf>,
+    repo_root: &str,
+    codec: &impl Codec,
+) -> Result<EffectMachine, Error>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let mut effects = EffectMachine::default();
+    let mut file_paths = vec![];
+
+    for file_path in files {
+        let file_name = file_path.to_string_lossy().into_owned();
+        let content = codec.decode(
+            fs_extra::file::read_to_string(&file_path).map_err(Error::FileCouldNotBeRead)?,
+        )?;
+        let raw: Value = serde_json::from_str(&content).map_err(|e| {
+            Error::FileCouldNotBeMigrated(
+                Box::new(Error::CouldNotParseDocument(e)),
+                file_name.clone(),
+            )
+        })?;
+        if !registry.is_outdated(&raw) {
+            continue;
+        }
+        let document: T = registry
+            .migrate(raw)
+            .and_then(|v| serde_json::from_value(v).map_err(Error::CouldNotParseDocument))
+            .map_err(|e| Error::FileCouldNotBeMigrated(Box::new(e), file_name.clone()))?;
+        effects.add(
+            EffectKind::WriteToFile(FileWriterOpts {
+                content: codec.encode(
+                    serde_json::to_string_pretty(&document)
+                        .map_err(Error::CouldNotSerializeDocument)?,
+                )?,
+                file_path,
+                can_create: false,
+                can_overwrite: true,
+            }),
+            false,
+        );
+        file_paths.push(file_name);
+    }
+
+    if file_paths.is_empty() {
+        effects.add(
+            EffectKind::PrintToStdOut(format!("every {} is up to date", registry.kind)),
+            false,
+        );
+        return Ok(effects);
+    }
+
+    effects.add(git::add(repo_root, &file_paths), false);
+    effects.add(
+        git::commit(
+            repo_root,
+            &format!(
+                "chore: migrate {} {} files to schema version {}",
+                file_paths.len(),
+                registry.kind,
+                registry.current_version()
+            ),
+        ),
+        false,
+    );
+    effects.add(git::pull(repo_root), false);
+    effects.add(git::push(repo_root), false);
+
+    Ok(effects)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0} schema version {1} is newer than this build knows about")]
+    UnsupportedSchemaVersion(&'static str, u32),
+    #[error("{0} document is not a json object")]
+    DocumentIsNotAnObject(&'static str),
+    #[error("migration step failed: {0}")]
+    StepFailed(String),
+    #[error("could not parse the document: {0}")]
+    CouldNotParseDocument(serde_json::Error),
+    #[error("could not serialize the document: {0}")]
+    CouldNotSerializeDocument(serde_json::Error),
+    #[error("could not read directory: {0}")]
+    DirCouldNotBeRead(fs_extra::error::Error),
+    #[error("file content could not be read: {0}")]
+    FileCouldNotBeRead(fs_extra::error::Error),
+    #[error("could not decode or encode the document: {0}")]
+    CodecFailed(String),
+    #[error("could not migrate {1}: {0}")]
+    FileCouldNotBeMigrated(Box<Self>, String),
+}
+
+#[cfg(test)]
+mod testing {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use rstest::*;
+
+    const fn is_normal<T: Sized + Send + Sync + Unpin>() {}
+
+    #[test]
+    const fn normal_types() {
+        is_normal::<Error>();
+        is_normal::<Registry>();
+    }
+
+    fn rename_name_to_title(mut document: Value) -> Result<Value, Error> {
+        let object = document
+            .as_object_mut()
+            .ok_or(Error::DocumentIsNotAnObject("test"))?;
+        let name = object.remove("name").unwrap_or(Value::Null);
+        object.insert("title".to_owned(), name);
+        Ok(document)
+    }
+
+    fn add_tags(mut document: Value) -> Result<Value, Error> {
+        document
+            .as_object_mut()
+            .ok_or(Error::DocumentIsNotAnObject("test"))?
+            .insert("tags".to_owned(), json!([]));
+        Ok(document)
+    }
+
+    const REGISTRY: Registry = Registry {
+        kind: "test",
+        steps: &[rename_name_to_title, add_tags],
+    };
+
+    #[rstest]
+    #[case::from_unversioned(json!({ "name": "x" }))]
+    #[case::from_v1(json!({ "schema_version": 1_u8, "name": "x" }))]
+    #[case::from_v2(json!({ "schema_version": 2_u8, "title": "x" }))]
+    #[case::already_current(json!({ "schema_version": 3_u8, "title": "x", "tags": [] }))]
+    fn migrates_step_by_step(#[case] document: Value) {
+        assert_eq!(
+            REGISTRY.migrate(document).unwrap(),
+            json!({ "schema_version": 3_u8, "title": "x", "tags": [] })
+        );
+    }
+
+    #[rstest]
+    fn refuses_the_future() {
+        assert!(REGISTRY.migrate(json!({ "schema_version": 4_u8 })).is_err());
+    }
+
+    #[rstest]
+    fn knows_what_is_outdated() {
+        assert_eq!(REGISTRY.current_version(), 3);
+        assert!(REGISTRY.is_outdated(&json!({ "name": "x" })));
+        assert!(!REGISTRY.is_outdated(&json!({ "schema_version": 3_u8 })));
+    }
+}
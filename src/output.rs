@@ -0,0 +1,188 @@
+use serde::Serialize;
+
+/// How listed items are written to the standard output.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, clap::ValueEnum)]
+pub enum Format {
+    /// Colourful, for people
+    #[default]
+    Pretty,
+    /// Every column in a table with a header
+    Table,
+    /// One json array
+    Json,
+    /// One json object per line
+    Ndjson,
+    /// Comma separated, with a header
+    Csv,
+    /// Tab separated, without a header
+    Plain,
+}
+
+/// Items that can be listed in the formats that are not pretty.
+pub trait Row: Serialize {
+    /// The names of the columns of the table and csv formats
+    fn columns() -> Vec<&'static str>;
+    /// The values of the columns, in the same order
+    fn cells(&self) -> Vec<String>;
+}
+
+impl<T: Row> Row for &T {
+    fn columns() -> Vec<&'static str> {
+        T::columns()
+    }
+    fn cells(&self) -> Vec<String> {
+        (*self).cells()
+    }
+}
+
+/// The items written in the format, `None` for the pretty format, which every list has its own
+/// way of writing.
+pub fn render<T: Row>(items: &[T], format: Format) -> Result<Option<String>, Error> {
+    Ok(Some(match format {
+        Format::Pretty => return Ok(None),
+        Format::Table => {
+            let mut table = comfy_table::Table::new();
+            table.load_preset(comfy_table::presets::UTF8_FULL_CONDENSED);
+            table.set_content_arrangement(comfy_table::ContentArrangement::Dynamic);
+            table.set_header(T::columns());
+            for item in items {
+                table.add_row(item.cells());
+            }
+            table.to_string()
+        }
+        Format::Json => serde_json::to_string_pretty(items).map_err(Error::CouldNotSerialize)?,
+        Format::Ndjson => items
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(Error::CouldNotSerialize)?
+            .join("\n"),
+        Format::Csv => std::iter::once(T::columns().into_iter().map(csv_field).collect())
+            .chain(
+                items
+                    .iter()
+                    .map(|item| item.cells().iter().map(|c| csv_field(c)).collect()),
+            )
+            .map(|fields: Vec<String>| fields.join(","))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Format::Plain => items
+            .iter()
+            .map(|item| {
+                item.cells()
+                    .iter()
+                    .map(|c| c.replace(['\t', '\n', '\r'], " "))
+                    .collect::<Vec<_>>()
+                    .join("\t")
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }))
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Whether the `NO_COLOR` environment variable asks for output without colours.
+#[must_use]
+pub fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+/// The text without its ANSI escape sequences.
+#[must_use]
+pub fn strip_ansi(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            stripped.push(c);
+            continue;
+        }
+        if chars.next_if_eq(&'[').is_some() {
+            // parameters and intermediates, up to the final byte of the sequence
+            while chars.next().is_some_and(|c| !('@'..='~').contains(&c)) {}
+        } else {
+            chars.next();
+        }
+    }
+    stripped
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("could not serialize the items: {0}")]
+    CouldNotSerialize(serde_json::Error),
+}
+
+#[cfg(test)]
+mod testing {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use rstest::*;
+
+    #[derive(Serialize)]
+    struct Item {
+        name: String,
+        count: u8,
+    }
+
+    impl Row for Item {
+        fn columns() -> Vec<&'static str> {
+            vec!["name", "count"]
+        }
+        fn cells(&self) -> Vec<String> {
+            vec![self.name.clone(), self.count.to_string()]
+        }
+    }
+
+    fn items() -> Vec<Item> {
+        vec![
+            Item {
+                name: "plain".to_owned(),
+                count: 1,
+            },
+            Item {
+                name: "has, \"quotes\"\nand lines".to_owned(),
+                count: 2,
+            },
+        ]
+    }
+
+    #[rstest]
+    #[case::pretty(Format::Pretty, None)]
+    #[case::ndjson(
+        Format::Ndjson,
+        Some("{\"name\":\"plain\",\"count\":1}\n{\"name\":\"has, \\\"quotes\\\"\\nand lines\",\"count\":2}")
+    )]
+    #[case::csv(
+        Format::Csv,
+        Some("name,count\nplain,1\n\"has, \"\"quotes\"\"\nand lines\",2")
+    )]
+    #[case::plain(Format::Plain, Some("plain\t1\nhas, \"quotes\" and lines\t2"))]
+    fn render_items(#[case] format: Format, #[case] expected: Option<&str>) {
+        assert_eq!(render(&items(), format).unwrap().as_deref(), expected);
+    }
+
+    #[rstest]
+    fn json_is_an_array() {
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&items(), Format::Json).unwrap().unwrap()).unwrap();
+        assert_eq!(json.as_array().map(Vec::len), Some(2));
+    }
+
+    #[rstest]
+    #[case::none("plain", "plain")]
+    #[case::colours("\u{1b}[1;31mred\u{1b}[0m and \u{1b}[7mmore", "red and more")]
+    fn strips_ansi(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(strip_ansi(text), expected);
+    }
+}
@@ -1,8 +1,16 @@
 pub mod bulk;
 pub mod effectors;
 pub mod error;
+pub mod focus;
+pub mod graph;
+pub mod migration;
+pub mod query;
+pub mod recurrence;
+pub mod sheet;
 pub mod task;
 pub mod tasklist;
+pub mod tracking;
+pub mod urgency;
 pub use error::*;
 
 // const FILE_NAME_FORMAT: &str = "%Y-%m-%d-%H-%M-%S.json";
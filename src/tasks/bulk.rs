@@ -1,14 +1,55 @@
+use std::str::FromStr;
+
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_while},
-    character::complete::{not_line_ending, tab},
-    combinator::{all_consuming, map},
-    error::{context, ContextError, ParseError, VerboseError},
-    sequence::{preceded, tuple},
-    Finish,
+    bytes::complete::{tag, take_till, take_till1},
+    character::complete::{multispace1, not_line_ending, tab},
+    combinator::{cut, eof, map, map_res, opt, value},
+    error::{context, ContextError, FromExternalError, ParseError, VerboseError, VerboseErrorKind},
+    multi::many0,
+    sequence::{preceded, terminated},
+    Finish, IResult,
 };
 
-use super::{task::Task, Error};
+use time::{Date, OffsetDateTime, UtcOffset};
+
+use super::{
+    recurrence::Recurrence,
+    task::{Area, AreaParsingError, Priority, PriorityParsingError, State, Task},
+    tasklist::{TaskDescription, TaskList},
+    Error,
+};
+use crate::timeutils;
+
+/// The tasks to open the bulk editor on, the ones still to do or backlogged unless every task is
+/// asked for. The filter matches a part of the title, or a whole project, person or area.
+#[must_use]
+pub fn select(tasks: &TaskList, filter: Option<&str>, all: bool) -> Vec<TaskDescription> {
+    tasks
+        .0
+        .iter()
+        .filter(|t| {
+            all || matches!(
+                t.task.current_state(),
+                Some(State::ToDo(_) | State::Backlog(_))
+            )
+        })
+        .filter(|t| {
+            let Some(filter) = filter else {
+                return true;
+            };
+            t.task.title.to_lowercase().contains(&filter.to_lowercase())
+                || t.task.projects.iter().any(|p| p == filter)
+                || t.task.people.iter().any(|p| p == filter)
+                || t.task
+                    .area
+                    .as_ref()
+                    .is_some_and(|a| a.to_string() == filter)
+        })
+        .cloned()
+        .collect()
+}
+
 pub fn form_file_action(tasks: &[Task]) -> String {
     tasks
         .iter()
@@ -17,95 +58,189 @@ pub fn form_file_action(tasks: &[Task]) -> String {
         .fold(String::new(), |accu, l| format!("{accu}{l}\n"))
 }
 
-pub fn multiple_lines(input: &str) -> Result<Vec<(u64, Action)>, Error> {
+/// The number of the task a line was written for, `None` for a new task, and its actions: the
+/// state verb first, then the title, then the edits of the fields.
+pub type Line = (Option<u64>, Vec<Action>);
+
+/// Blank lines are skipped, errors point at the line and column that could not be parsed.
+pub fn multiple_lines(input: &str) -> Result<Vec<Line>, Error> {
     input
         .lines()
-        .map(action_from_line)
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| parse_line(i + 1, l))
         .try_fold(vec![], |mut a, x| {
             a.push(x?);
             Ok(a)
         })
 }
-pub fn action_from_line(input: &str) -> Result<(u64, Action), Error> {
+
+pub fn action_from_line(input: &str) -> Result<Line, Error> {
+    parse_line(1, input)
+}
+
+fn parse_line(line: usize, input: &str) -> Result<Line, Error> {
     let (_, res) = line_parser::<VerboseError<&str>>(input)
         .finish()
-        .map_err(|e| Error::ParsingLineFailed(Box::new(e.to_string())))?;
+        .map_err(|e| {
+            let (column, expected) = failed_at(input, &e, "a valid line");
+            Error::ParsingLineFailed(line, column, expected)
+        })?;
     Ok(res)
 }
 
-fn line_parser<'a, E>(input: &'a str) -> Result<(&'a str, (u64, Action)), nom::Err<E>>
+/// The column where the innermost parser failed and what it expected there, the innermost context
+/// or the fallback when there is none.
+pub(crate) fn failed_at(input: &str, e: &VerboseError<&str>, fallback: &str) -> (usize, String) {
+    let offset = e
+        .errors
+        .first()
+        .map_or(0, |(rest, _)| input.len().saturating_sub(rest.len()));
+    let column = input.get(..offset).map_or(0, |s| s.chars().count()) + 1;
+    let expected = e
+        .errors
+        .iter()
+        .find_map(|(_, kind)| match kind {
+            VerboseErrorKind::Context(c) => Some(*c),
+            _ => None,
+        })
+        .unwrap_or(fallback);
+    (column, expected.to_owned())
+}
+
+/// `[number<TAB>]verb<TAB>title[<TAB>edit...][<TAB>reason]`
+///
+/// Lines without a number add a new task. The edits are separated by white space:
+/// - `due:<date>` sets the deadline, in the `timeutils` syntax, `due:` alone clears it
+/// - `start:<date>` sets the best starting time the same way
+/// - `+?project` and `-?project` add and remove a project
+/// - `+@person` and `-@person` add and remove a person
+/// - `+#area` sets the area, `-#` clears it
+/// - `+!priority` sets the priority, `-!` clears it
+///
+/// Only `aban` takes a reason, after the edits.
+fn line_parser<'a, E>(input: &'a str) -> IResult<&'a str, Line, E>
 where
-    E: ParseError<&'a str> + ContextError<&'a str>,
+    E: ParseError<&'a str>
+        + ContextError<&'a str>
+        + FromExternalError<&'a str, timeutils::Error>
+        + FromExternalError<&'a str, AreaParsingError>
+        + FromExternalError<&'a str, PriorityParsingError>,
 {
-    all_consuming(tuple((
-        context(
-            "parsing number at the start of the line",
-            nom::character::complete::u64,
+    let (input, number) = opt(terminated(
+        context("task number", nom::character::complete::u64),
+        tab,
+    ))(input)?;
+    let (input, verb) = context(
+        "action, one of done, aban, ignr, back or todo",
+        alt((
+            value(Action::Done, tag("done")),
+            value(Action::Abandon(None), tag("aban")),
+            value(Action::Ignore, tag("ignr")),
+            value(Action::Backlog, tag("back")),
+            value(Action::ToDo, tag("todo")),
+        )),
+    )(input)?;
+    let (input, title) = preceded(
+        context("tab before the title", tab),
+        context("title", take_till1(|c| c == '\t')),
+    )(input)?;
+    let (input, edits) = many0(preceded(multispace1, edit))(input)?;
+    let (input, verb) = match verb {
+        Action::Abandon(_) => map(
+            opt(preceded(tab, context("reason", not_line_ending))),
+            |reason: Option<&str>| Action::Abandon(reason.map(ToOwned::to_owned)),
+        )(input)?,
+        verb => (input, verb),
+    };
+    let (input, _) = context("an edit or the end of the line", eof)(input)?;
+
+    let mut actions = vec![verb, Action::Title(title.trim().to_owned())];
+    actions.extend(edits);
+    Ok((input, (number, actions)))
+}
+
+fn edit<'a, E>(input: &'a str) -> IResult<&'a str, Action, E>
+where
+    E: ParseError<&'a str>
+        + ContextError<&'a str>
+        + FromExternalError<&'a str, timeutils::Error>
+        + FromExternalError<&'a str, AreaParsingError>
+        + FromExternalError<&'a str, PriorityParsingError>,
+{
+    let word = || take_till1(char::is_whitespace);
+    alt((
+        preceded(
+            tag("due:"),
+            cut(context("deadline", map(date(), Action::Deadline))),
         ),
         preceded(
-            context("tab before action", tab),
-            context(
-                "getting action",
-                alt((
-                    map(
-                        tuple((tag("done"), tab, context("title", not_line_ending))),
-                        |_| Action::Done,
-                    ),
-                    map(
-                        preceded(
-                            tuple((
-                                tag("aban"),
-                                tab,
-                                context(
-                                    "title",
-                                    take_while(|x: char| !x.eq_ignore_ascii_case(&'\t')),
-                                ),
-                            )),
-                            preceded(tab, context("reason", not_line_ending)),
-                        ),
-                        |reason: &'a str| Action::Abandon(Some(reason.to_owned())),
-                    ),
-                    map(
-                        tuple((tag("aban"), tab, context("title", not_line_ending))),
-                        |_| Action::Abandon(None),
-                    ),
-                    map(
-                        tuple((
-                            tag("ignr"),
-                            tab,
-                            context(
-                                "title",
-                                take_while(|x: char| !x.eq_ignore_ascii_case(&'\t')),
-                            ),
-                        )),
-                        |_| Action::Ignore,
-                    ),
-                    map(
-                        tuple((
-                            tag("back"),
-                            tab,
-                            context(
-                                "title",
-                                take_while(|x: char| !x.eq_ignore_ascii_case(&'\t')),
-                            ),
-                        )),
-                        |_| Action::Backlog,
-                    ),
-                    map(
-                        tuple((
-                            tag("todo"),
-                            tab,
-                            context(
-                                "title",
-                                take_while(|x: char| !x.eq_ignore_ascii_case(&'\t')),
-                            ),
-                        )),
-                        |_| Action::ToDo,
-                    ),
-                )),
-            ),
+            tag("start:"),
+            cut(context("start", map(date(), Action::Start))),
         ),
-    )))(input)
+        preceded(
+            tag("+?"),
+            cut(context(
+                "project",
+                map(word(), |p: &str| Action::AddProject(p.to_owned())),
+            )),
+        ),
+        preceded(
+            tag("-?"),
+            cut(context(
+                "project",
+                map(word(), |p: &str| Action::RemoveProject(p.to_owned())),
+            )),
+        ),
+        preceded(
+            tag("+@"),
+            cut(context(
+                "person",
+                map(word(), |p: &str| Action::AddPerson(p.to_owned())),
+            )),
+        ),
+        preceded(
+            tag("-@"),
+            cut(context(
+                "person",
+                map(word(), |p: &str| Action::RemovePerson(p.to_owned())),
+            )),
+        ),
+        preceded(
+            tag("+#"),
+            cut(context(
+                "area, one of work, home or personal",
+                map_res(word(), |a: &str| {
+                    Area::from_str(a).map(|a| Action::Area(Some(a)))
+                }),
+            )),
+        ),
+        value(Action::Area(None), tag("-#")),
+        preceded(
+            tag("+!"),
+            cut(context(
+                "priority, one of low, medium or high",
+                map_res(word(), |p: &str| {
+                    Priority::from_str(p).map(|p| Action::Priority(Some(p)))
+                }),
+            )),
+        ),
+        value(Action::Priority(None), tag("-!")),
+    ))(input)
+}
+
+/// A date in the `timeutils` syntax, an empty one is `None`. It is only checked here, relative
+/// dates are resolved when the edit is applied.
+fn date<'a, E>() -> impl FnMut(&'a str) -> IResult<&'a str, Option<String>, E>
+where
+    E: ParseError<&'a str> + FromExternalError<&'a str, timeutils::Error>,
+{
+    map_res(take_till(char::is_whitespace), |d: &str| {
+        if d.is_empty() {
+            return Ok(None);
+        }
+        timeutils::parse_date(d, UtcOffset::UTC).map(|_| Some(d.to_owned()))
+    })
 }
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -116,82 +251,221 @@ pub enum Action {
     ToDo,
     Abandon(Option<String>),
     Backlog,
+    /// The title as it is in the line
+    Title(String),
+    /// The deadline as it was written, `None` clears it
+    Deadline(Option<String>),
+    /// The best starting time as it was written, `None` clears it
+    Start(Option<String>),
+    /// `None` clears the description
+    Description(Option<String>),
+    AddProject(String),
+    RemoveProject(String),
+    AddPerson(String),
+    RemovePerson(String),
+    /// `None` clears the area
+    Area(Option<Area>),
+    /// `None` stops the task from coming back
+    Repeat(Option<Recurrence>),
+    /// `None` clears the priority
+    Priority(Option<Priority>),
+}
+
+impl Action {
+    /// The state the task moves to, `None` when it is left alone or the action edits a field.
+    #[must_use]
+    pub fn state(&self, now: OffsetDateTime) -> Option<State> {
+        match self {
+            Self::Done => Some(State::Done(now)),
+            Self::ToDo => Some(State::ToDo(now)),
+            Self::Abandon(reason) => Some(State::Abandoned(now, reason.clone())),
+            Self::Backlog => Some(State::Backlog(now)),
+            _ => None,
+        }
+    }
+
+    /// Changes the task, a state is only added when the task is not already in a state of that kind, and relative
+    /// deadlines are resolved from `now`.
+    pub fn apply(&self, task: &mut Task, now: OffsetDateTime) -> Result<(), Error> {
+        match self {
+            Self::Ignore => {}
+            Self::Done | Self::ToDo | Self::Abandon(_) | Self::Backlog => {
+                if let Some(state) = self.state(now) {
+                    if task.current_state().map(std::mem::discriminant)
+                        != Some(std::mem::discriminant(&state))
+                    {
+                        task.state_log.push(state);
+                    }
+                }
+            }
+            Self::Title(title) => title.clone_into(&mut task.title),
+            Self::Deadline(deadline) => task.end = resolve(deadline.as_deref(), now)?,
+            Self::Start(start) => task.start = resolve(start.as_deref(), now)?,
+            Self::Description(description) => description.clone_into(&mut task.description),
+            Self::AddProject(project) => add(&mut task.projects, project),
+            Self::RemoveProject(project) => task.projects.retain(|p| p != project),
+            Self::AddPerson(person) => add(&mut task.people, person),
+            Self::RemovePerson(person) => task.people.retain(|p| p != person),
+            Self::Area(area) => area.clone_into(&mut task.area),
+            Self::Repeat(recurrence) => recurrence.clone_into(&mut task.recurrence),
+            Self::Priority(priority) => task.priority = *priority,
+        }
+        Ok(())
+    }
+}
+
+fn resolve(date: Option<&str>, now: OffsetDateTime) -> Result<Option<Date>, Error> {
+    date.map(|d| timeutils::parse_date(d, now.offset()))
+        .transpose()
+        .map_err(Error::DateCouldNotBeParsed)
+}
+
+fn add(values: &mut Vec<String>, value: &str) {
+    if !values.iter().any(|v| v == value) {
+        values.push(value.to_owned());
+    }
 }
+
 #[cfg(test)]
 mod testing {
-    use crate::tasks::task::State;
+    #![allow(clippy::unwrap_used, clippy::indexing_slicing)]
 
     #[allow(clippy::wildcard_imports)]
     use super::*;
     use rstest::*;
 
+    fn line(number: Option<u64>, verb: Action, title: &str) -> Line {
+        (number, vec![verb, Action::Title(title.to_owned())])
+    }
+
     #[rstest]
-    #[case::ignr("1\tignr\ttask description, done ", (1, Action::Ignore))]
-    #[case::done("0\tdone\ttask description", (0, Action::Done))]
-    #[case::todo("2\ttodo\ttask description",(2, Action::ToDo))]
-    #[case::aban("10\taban\ttask description",(10, Action::Abandon(None)))]
-    #[case::aban_with_reason("10\taban\ttask description\tsome reason",(10, Action::Abandon(Some("some reason".to_owned()))))]
-    #[case::back("01\tback\ttask description", (1, Action::Backlog))]
-    fn bulk_parser_oneline_happy(#[case] input: &str, #[case] expect: (u64, Action)) {
+    #[case::ignr(
+        "1\tignr\ttask description, done ",
+        line(Some(1), Action::Ignore, "task description, done")
+    )]
+    #[case::done(
+        "0\tdone\ttask description",
+        line(Some(0), Action::Done, "task description")
+    )]
+    #[case::todo(
+        "2\ttodo\ttask description",
+        line(Some(2), Action::ToDo, "task description")
+    )]
+    #[case::aban(
+        "10\taban\ttask description",
+        line(Some(10), Action::Abandon(None), "task description")
+    )]
+    #[case::aban_with_reason("10\taban\ttask description\tsome reason",line(Some(10), Action::Abandon(Some("some reason".to_owned())), "task description"))]
+    #[case::back(
+        "01\tback\ttask description",
+        line(Some(1), Action::Backlog, "task description")
+    )]
+    #[case::new_task("todo\tnew one", line(None, Action::ToDo, "new one"))]
+    fn bulk_parser_oneline_happy(#[case] input: &str, #[case] expect: Line) {
         assert_eq!(action_from_line(input).unwrap(), expect);
     }
     #[rstest]
-    #[case::ignr("\tignr\ttask description, done\t ", (1, Action::Ignore))]
-    #[case::done("done\ttask description\t", (0, Action::Done))]
-    #[case::todo("2\ttodo\ttask description\t0",(2, Action::ToDo))]
-    #[case::aban("10 10\taban\ttask description",(10, Action::Abandon(None)))]
-    #[case::aban_with_reason("1 0\taban\ttask description\tsome reason",(10, Action::Abandon(Some("some reason".to_owned()))))]
-    #[case::back("01 back ttask description", (1, Action::Backlog))]
+    #[case::ignr(
+        "\tignr\ttask description, done\t ",
+        line(Some(1), Action::Ignore, "task description, done")
+    )]
+    #[case::done(
+        "done\ttask description\t",
+        line(Some(0), Action::Done, "task description")
+    )]
+    #[case::todo(
+        "2\ttodo\ttask description\t0",
+        line(Some(2), Action::ToDo, "task description")
+    )]
+    #[case::aban(
+        "10 10\taban\ttask description",
+        line(Some(10), Action::Abandon(None), "task description")
+    )]
+    #[case::aban_with_reason("1 0\taban\ttask description\tsome reason",line(Some(10), Action::Abandon(Some("some reason".to_owned())), "task description"))]
+    #[case::back(
+        "01 back ttask description",
+        line(Some(1), Action::Backlog, "task description")
+    )]
     #[should_panic]
-    fn bulk_parser_oneline_not_happy(#[case] input: &str, #[case] expect: (u64, Action)) {
+    fn bulk_parser_oneline_not_happy(#[case] input: &str, #[case] expect: Line) {
         assert_eq!(action_from_line(input).unwrap(), expect);
     }
     #[rstest]
-    #[case::ignr_multiple("1\tignr\ttask description, done \n0\tdone\ttask description\n10\taban\ttask description\tsome reason\n", vec![(1, Action::Ignore), (0, Action::Done),(10, Action::Abandon(Some("some reason".to_owned())))])]
-    #[case::done_single("0\tdone\ttask description\n", vec![(0, Action::Done)])]
-    fn bulk_parser_multiline(#[case] input: &str, #[case] expect: Vec<(u64, Action)>) {
+    #[case::ignr_multiple("1\tignr\ttask description, done \n0\tdone\ttask description\n10\taban\ttask description\tsome reason\n", vec![line(Some(1), Action::Ignore, "task description, done"), line(Some(0), Action::Done, "task description"),line(Some(10), Action::Abandon(Some("some reason".to_owned())), "task description")])]
+    #[case::done_single("0\tdone\ttask description\n", vec![line(Some(0), Action::Done, "task description")])]
+    fn bulk_parser_multiline(#[case] input: &str, #[case] expect: Vec<Line>) {
         assert_eq!(multiple_lines(input).unwrap(), expect);
     }
+    #[rstest]
+    fn bulk_parser_edits() {
+        let (number, actions) = action_from_line(
+            "3\taban\trenamed\t+?prmait -?old +@bob\t-@alice +#work -# due:2d due: start:tom +!high -!\tno time",
+        )
+        .unwrap();
+        assert_eq!(number, Some(3));
+        assert_eq!(
+            actions,
+            vec![
+                Action::Abandon(Some("no time".to_owned())),
+                Action::Title("renamed".to_owned()),
+                Action::AddProject("prmait".to_owned()),
+                Action::RemoveProject("old".to_owned()),
+                Action::AddPerson("bob".to_owned()),
+                Action::RemovePerson("alice".to_owned()),
+                Action::Area(Some(Area::Work)),
+                Action::Area(None),
+                Action::Deadline(Some("2d".to_owned())),
+                Action::Deadline(None),
+                Action::Start(Some("tom".to_owned())),
+                Action::Priority(Some(Priority::High)),
+                Action::Priority(None),
+            ]
+        );
+    }
+    #[rstest]
+    #[case::number("0\tdone\tfirst\n\n1 done\tsecond", "line 3, column 1 of the bulk actions: expected action, one of done, aban, ignr, back or todo")]
+    #[case::deadline(
+        "0\tdone\tfirst\t+?prmait due:someday",
+        "line 1, column 27 of the bulk actions: expected deadline"
+    )]
+    #[case::area(
+        "0\tdone\tfirst\t+#garden",
+        "line 1, column 16 of the bulk actions: expected area, one of work, home or personal"
+    )]
+    #[case::unknown_edit(
+        "0\tdone\tfirst\tprmait",
+        "line 1, column 13 of the bulk actions: expected an edit or the end of the line"
+    )]
+    fn bulk_parser_errors_point_at_the_column(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(multiple_lines(input).unwrap_err().to_string(), expected);
+    }
+    #[rstest]
+    fn actions_change_the_task(tasks: (Vec<Task>, String)) {
+        let now = time::OffsetDateTime::now_utc();
+        let mut task = tasks.0[0].clone();
+        task.projects.push("old".to_owned());
+        let (_, actions) =
+            action_from_line("0\ttodo\trenamed\t+?prmait -?old +?prmait +#home due:1d").unwrap();
+        for action in &actions {
+            action.apply(&mut task, now).unwrap();
+        }
+        assert_eq!(task.title, "renamed");
+        assert_eq!(task.projects, vec!["prmait".to_owned()]);
+        assert_eq!(task.area, Some(Area::Home));
+        assert_eq!(
+            task.end,
+            Some(crate::timeutils::day_from_today(now.offset(), 1))
+        );
+        // already to do
+        assert_eq!(task.state_log.len(), 1);
+    }
     #[fixture]
     fn tasks() -> (Vec<Task>, String) {
         let now = time::OffsetDateTime::now_utc();
         let v = vec![
-            Task {
-                id: now.unix_timestamp(),
-                time_created: now,
-                state_log: vec![State::ToDo(now)],
-                title: "first one".to_owned(),
-                description: None,
-                area: None,
-                people: vec![],
-                projects: vec![],
-                start: None,
-                end: None,
-            },
-            Task {
-                id: now.unix_timestamp(),
-                time_created: now,
-                state_log: vec![State::ToDo(now)],
-                title: "second task".to_owned(),
-                description: None,
-                area: None,
-                people: vec![],
-                projects: vec![],
-                start: None,
-                end: None,
-            },
-            Task {
-                id: now.unix_timestamp(),
-                time_created: now,
-                state_log: vec![State::ToDo(now)],
-                title: "third task".to_owned(),
-                description: None,
-                area: None,
-                people: vec![],
-                projects: vec![],
-                start: None,
-                end: None,
-            },
+            Task::new("first one".to_owned(), now),
+            Task::new("second task".to_owned(), now),
+            Task::new("third task".to_owned(), now),
         ];
         (
             v,
@@ -202,4 +476,42 @@ mod testing {
     fn test_the_tasks(tasks: (Vec<Task>, String)) {
         assert_eq!(form_file_action(&tasks.0), tasks.1);
     }
+    #[rstest]
+    #[case::everything_open(None, false, vec!["first one", "third task"])]
+    #[case::title(Some("TASK"), false, vec!["third task"])]
+    #[case::project(Some("prmait"), true, vec!["second task"])]
+    #[case::all(None, true, vec!["first one", "second task", "third task"])]
+    fn select_tasks(
+        tasks: (Vec<Task>, String),
+        #[case] filter: Option<&str>,
+        #[case] all: bool,
+        #[case] expected: Vec<&str>,
+    ) {
+        let mut list = tasks.0;
+        let now = time::OffsetDateTime::now_utc();
+        if let Some(second) = list.get_mut(1) {
+            second.state_log.push(State::Done(now));
+            second.projects.push("prmait".to_owned());
+        }
+        let list = TaskList(
+            list.into_iter()
+                .map(|task| TaskDescription {
+                    task,
+                    file_name: String::new(),
+                })
+                .collect(),
+        );
+        let selected: Vec<String> = select(&list, filter, all)
+            .into_iter()
+            .map(|t| t.task.title)
+            .collect();
+        assert_eq!(selected, expected);
+    }
+    #[rstest]
+    fn blank_lines_are_skipped() {
+        assert_eq!(
+            multiple_lines("\n0\tdone\tfirst\n  \n").unwrap(),
+            vec![line(Some(0), Action::Done, "first")]
+        );
+    }
 }
@@ -4,15 +4,28 @@ use color_eyre::owo_colors::OwoColorize;
 use time::formatting::Formattable;
 use time::{Date, OffsetDateTime};
 
-use crate::effects::{CreateDirOpts, Effect, EffectKind, EffectMachine, FileWriterOpts};
+use crate::context::Context;
+use crate::effects::{
+    CreateDirOpts, DraftWriterOpts, Effect, EffectKind, EffectMachine, FileWriterOpts,
+    OpenInEditorOpts,
+};
 use crate::files::ToFileName;
 use crate::git;
+use crate::journal::{Book, Period};
+use crate::links::{self, Backlinks, Link};
+use crate::output;
 
 use super::Result;
 use super::{
+    bulk,
+    focus::{self, Session},
+    graph, migration,
+    query::Query,
+    sheet,
     task::{State, Task},
-    tasklist::TaskList,
-    Error,
+    tasklist::{TaskDescription, TaskList},
+    tracking::{self, Interval},
+    urgency, Error,
 };
 
 pub fn new_task(
@@ -60,13 +73,373 @@ pub fn new_task(
     Ok(effects)
 }
 
+/// Writes the tasks as bulk action lines into the draft file and opens it in the editor, the
+/// actions can be applied with [`apply_bulk_actions`] after the editor exits. The draft belongs in
+/// a [`DraftDir`](crate::files::DraftDir), which removes it.
+pub fn open_bulk_editor(
+    tasks: &[TaskDescription],
+    draft_path: &Path,
+    editor: String,
+) -> Result<EffectMachine> {
+    let mut effects = EffectMachine::default();
+
+    let tasks: Vec<Task> = tasks.iter().map(|t| t.task.clone()).collect();
+    effects.add(
+        EffectKind::WriteToDraft(DraftWriterOpts {
+            content: bulk::form_file_action(&tasks).into_bytes(),
+            file_path: draft_path.to_path_buf(),
+        }),
+        false,
+    );
+    effects.add(
+        EffectKind::OpenInEditor(OpenInEditorOpts {
+            editor,
+            files_to_edit: vec![draft_path.to_path_buf()],
+        }),
+        false,
+    );
+
+    Ok(effects)
+}
+
+/// Parses the draft written by [`open_bulk_editor`] for the same tasks and applies the actions on
+/// every line to the task it was written for, lines without a number add new tasks. Recurring
+/// tasks that are done are followed by their next instance. Everything is written in a single
+/// commit.
+pub fn apply_bulk_actions(
+    task_dir: &Path,
+    tasks: &[TaskDescription],
+    draft_path: &Path,
+    repo_root: &str,
+    now: OffsetDateTime,
+    context: Option<&Context>,
+    time_format_descriptor: &(impl Formattable + ?Sized),
+) -> Result<EffectMachine> {
+    let mut effects = EffectMachine::default();
+
+    let content = fs_extra::file::read_to_string(draft_path).map_err(Error::FileCouldNotBeRead)?;
+    let mut changed = vec![];
+    let mut created: i64 = 0;
+    for (line, actions) in bulk::multiple_lines(&content)? {
+        let (original, file_name, can_create) = match line {
+            Some(line) => {
+                let description = usize::try_from(line)
+                    .ok()
+                    .and_then(|i| tasks.get(i))
+                    .ok_or(Error::BulkLineDoesNotMatchATask(line))?;
+                (
+                    description.task.clone(),
+                    description.file_name.clone(),
+                    false,
+                )
+            }
+            None => {
+                // every new task gets its own second, ids and file names come from it
+                let time_created = now + time::Duration::seconds(created);
+                created += 1;
+                let task = Task {
+                    state_log: vec![],
+                    context: context.cloned(),
+                    ..Task::new(String::new(), time_created)
+                };
+                let file_name = task.to_file_name(time_format_descriptor)?;
+                (task, file_name, true)
+            }
+        };
+        let mut task = original.clone();
+        for action in &actions {
+            action.apply(&mut task, now)?;
+        }
+        // a new task that was ignored has no state
+        if task == original || task.state_log.is_empty() {
+            continue;
+        }
+        task.validate()?;
+
+        let file_path = task_dir.join(&file_name);
+        if can_create {
+            effects.add(
+                EffectKind::CreateDir(CreateDirOpts {
+                    folder_path: task_dir.to_path_buf(),
+                    ok_if_exists: true,
+                }),
+                false,
+            );
+        }
+        effects.add(
+            EffectKind::WriteToFile(FileWriterOpts {
+                content: serde_json::to_string_pretty(&task)
+                    .map_err(|e| Error::FileCouldNotSerializeEntryIntoJson(e, file_name))?
+                    .into_bytes(),
+                file_path: file_path.clone(),
+                can_create,
+                can_overwrite: !can_create,
+            }),
+            false,
+        );
+        changed.push(file_path.to_string_lossy().into_owned());
+
+        let was_done = matches!(original.current_state(), Some(State::Done(_)));
+        if let (false, Some(State::Done(_))) = (was_done, task.current_state()) {
+            let done_at = now + time::Duration::seconds(created);
+            if let Some(file_path) = write_next_instance(
+                &mut effects,
+                task_dir,
+                &task,
+                done_at,
+                time_format_descriptor,
+            )? {
+                created += 1;
+                changed.push(file_path);
+            }
+        }
+    }
+
+    if changed.is_empty() {
+        effects.add(
+            EffectKind::PrintToStdOut("no task was changed".to_owned()),
+            false,
+        );
+    } else {
+        effects.add(git::add(repo_root, &changed), false);
+        effects.add(
+            git::commit(
+                repo_root,
+                &format!("feat(tasks): bulk update {} tasks", changed.len()),
+            ),
+            false,
+        );
+        effects.add(git::pull(repo_root), false);
+        effects.add(git::push(repo_root), false);
+    }
+
+    Ok(effects)
+}
+
+/// Applies the edits to the task and writes it back in its own commit, after checking that the
+/// edited task still makes sense.
+pub fn edit_task(
+    task_dir: &Path,
+    the_task_description: &TaskDescription,
+    actions: &[bulk::Action],
+    repo_root: &str,
+    now: OffsetDateTime,
+) -> Result<EffectMachine> {
+    let mut effects = EffectMachine::default();
+
+    let mut task = the_task_description.task.clone();
+    for action in actions {
+        action.apply(&mut task, now)?;
+    }
+    task.validate()?;
+    if task == the_task_description.task {
+        effects.add(
+            EffectKind::PrintToStdOut(format!("task {} was not changed", task.id)),
+            false,
+        );
+        return Ok(effects);
+    }
+
+    let file_path = task_dir.join(&the_task_description.file_name);
+    effects.add(
+        EffectKind::WriteToFile(FileWriterOpts {
+            content: serde_json::to_string_pretty(&task)
+                .map_err(|e| {
+                    Error::FileCouldNotSerializeEntryIntoJson(
+                        e,
+                        the_task_description.file_name.clone(),
+                    )
+                })?
+                .into_bytes(),
+            file_path: file_path.clone(),
+            can_create: false,
+            can_overwrite: true,
+        }),
+        false,
+    );
+    let fp = file_path.to_string_lossy().into_owned();
+    effects.add(git::add(repo_root, &[fp]), false);
+    effects.add(
+        git::commit(repo_root, &format!("feat(tasks): edit task {}", task.id)),
+        false,
+    );
+    effects.add(git::pull(repo_root), false);
+    effects.add(git::push(repo_root), false);
+
+    Ok(effects)
+}
+
+/// Writes the task as a sheet into the draft file and opens it in the editor, the sheet can be
+/// applied with [`edit_task_from_sheet`] after the editor exits. The draft belongs in a
+/// [`DraftDir`](crate::files::DraftDir), which removes it.
+pub fn open_task_sheet(task: &Task, draft_path: &Path, editor: String) -> Result<EffectMachine> {
+    let mut effects = EffectMachine::default();
+
+    effects.add(
+        EffectKind::WriteToDraft(DraftWriterOpts {
+            content: sheet::render(task).into_bytes(),
+            file_path: draft_path.to_path_buf(),
+        }),
+        false,
+    );
+    effects.add(
+        EffectKind::OpenInEditor(OpenInEditorOpts {
+            editor,
+            files_to_edit: vec![draft_path.to_path_buf()],
+        }),
+        false,
+    );
+
+    Ok(effects)
+}
+
+/// Parses the sheet written by [`open_task_sheet`] for the same task and edits the task like
+/// [`edit_task`] does.
+pub fn edit_task_from_sheet(
+    task_dir: &Path,
+    the_task_description: &TaskDescription,
+    draft_path: &Path,
+    repo_root: &str,
+    now: OffsetDateTime,
+) -> Result<EffectMachine> {
+    let content = fs_extra::file::read_to_string(draft_path).map_err(Error::FileCouldNotBeRead)?;
+    let actions = sheet::actions(&content, &the_task_description.task)?;
+    edit_task(task_dir, the_task_description, &actions, repo_root, now)
+}
+
+/// Makes the task depend on the other tasks, refusing any that would make a task depend on
+/// itself.
+pub fn add_dependencies(
+    task_dir: &Path,
+    tasks_list: &TaskList,
+    task_identifier: i64,
+    dependency_identifiers: &[i64],
+    repo_root: &str,
+) -> Result<EffectMachine> {
+    let the_task_description = tasks_list.find(task_identifier)?;
+    let mut task = the_task_description.task.clone();
+    for di in dependency_identifiers {
+        let dependency = tasks_list.find(*di)?.task.id;
+        if tasks_list.would_cycle(task.id, dependency) {
+            return Err(Error::DependencyCycle(task.id, dependency));
+        }
+        if !task.depends_on.contains(&dependency) {
+            task.depends_on.push(dependency);
+        }
+    }
+    write_dependencies(task_dir, the_task_description, &task, repo_root)
+}
+
+/// Stops the task from depending on the other tasks.
+pub fn remove_dependencies(
+    task_dir: &Path,
+    tasks_list: &TaskList,
+    task_identifier: i64,
+    dependency_identifiers: &[i64],
+    repo_root: &str,
+) -> Result<EffectMachine> {
+    let the_task_description = tasks_list.find(task_identifier)?;
+    let mut task = the_task_description.task.clone();
+    for di in dependency_identifiers {
+        // the dependency may be gone from the list already
+        let dependency = tasks_list.find(*di).map_or(*di, |d| d.task.id);
+        if !task.depends_on.contains(&dependency) {
+            return Err(Error::NotADependency(task.id, dependency));
+        }
+        task.depends_on.retain(|d| *d != dependency);
+    }
+    write_dependencies(task_dir, the_task_description, &task, repo_root)
+}
+
+fn write_dependencies(
+    task_dir: &Path,
+    the_task_description: &TaskDescription,
+    task: &Task,
+    repo_root: &str,
+) -> Result<EffectMachine> {
+    let mut effects = EffectMachine::default();
+    if task == &the_task_description.task {
+        effects.add(
+            EffectKind::PrintToStdOut(format!("task {} was not changed", task.id)),
+            false,
+        );
+        return Ok(effects);
+    }
+
+    let file_path = task_dir.join(&the_task_description.file_name);
+    effects.add(
+        EffectKind::WriteToFile(FileWriterOpts {
+            content: serde_json::to_string_pretty(task)
+                .map_err(|e| {
+                    Error::FileCouldNotSerializeEntryIntoJson(
+                        e,
+                        the_task_description.file_name.clone(),
+                    )
+                })?
+                .into_bytes(),
+            file_path: file_path.clone(),
+            can_create: false,
+            can_overwrite: true,
+        }),
+        false,
+    );
+    let fp = file_path.to_string_lossy().into_owned();
+    effects.add(git::add(repo_root, &[fp]), false);
+    effects.add(
+        git::commit(
+            repo_root,
+            &format!("feat(tasks): update the dependencies of task {}", task.id),
+        ),
+        false,
+    );
+    effects.add(git::pull(repo_root), false);
+    effects.add(git::push(repo_root), false);
+
+    Ok(effects)
+}
+
+/// Prints the open tasks that depend on others as a tree, or as a DOT graph.
+pub fn dependency_graph(tasks_list: &TaskList, as_dot: bool) -> Result<EffectMachine> {
+    let mut effects = EffectMachine::default();
+    let rendered = if as_dot {
+        graph::dot(tasks_list)
+    } else {
+        graph::tree(tasks_list)
+    };
+    effects.add(
+        EffectKind::PrintToStdOut(if rendered.is_empty() {
+            "no open task depends on another".to_owned()
+        } else {
+            rendered
+        }),
+        false,
+    );
+    Ok(effects)
+}
+
+/// Rewrites every task file that was written with an older schema, in a single commit.
+pub fn migrate_tasks(task_dir: &Path, repo_root: &str) -> Result<EffectMachine> {
+    Ok(crate::migration::rewrite_outdated::<Task>(
+        &migration::REGISTRY,
+        crate::files::list_json(task_dir).map_err(Error::DirCouldNotBeRead)?,
+        repo_root,
+        &crate::migration::Plain,
+    )?)
+}
+
+/// Moves the tasks to the state, one commit per task. A task can only be done or abandoned while
+/// some of its parts are still open when `recursive` is set, the open parts then move with it.
+/// Recurring tasks that are done are followed by their next instance in the same commit.
 pub fn mark_task_as(
     task_dir: &Path,
     tasks_list: &TaskList,
     state: &State,
     repo_root: &str,
     task_identifier: &[i64],
+    recursive: bool,
+    time_format_descriptor: &(impl Formattable + ?Sized),
 ) -> Result<EffectMachine> {
+    let mut created: i64 = 0;
     task_identifier
         .iter()
         .try_fold(EffectMachine::default(), |mut effects, ti| {
@@ -83,35 +456,77 @@ pub fn mark_task_as(
                 return Err(Error::MoreThanOneTaskWasFound(Box::new(tasks)));
             }
 
-            let mut the_task_description = tasks.get(0).ok_or(Error::NoTasksFound)?.to_owned();
-            the_task_description.task.state_log.push(state.clone());
+            let the_task_description = tasks.first().ok_or(Error::NoTasksFound)?.to_owned();
+            let open_parts = if matches!(state, State::Done(_) | State::Abandoned(_, _)) {
+                tasks_list.open_descendants(the_task_description.task.id)
+            } else {
+                vec![]
+            };
+            if !open_parts.is_empty() && !recursive {
+                return Err(Error::TaskHasOpenParts(
+                    the_task_description.task.id,
+                    open_parts.len(),
+                ));
+            }
 
-            let file_path = task_dir.join(&the_task_description.file_name);
-            let new_file_content = serde_json::to_string_pretty(&the_task_description.task)
-                .map_err(|e| {
-                    Error::FileCouldNotSerializeEntryIntoJson(e, the_task_description.file_name)
-                })?
-                .into_bytes();
-            effects.add(
-                EffectKind::WriteToFile(FileWriterOpts {
-                    content: new_file_content,
-                    file_path: file_path.clone(),
-                    can_create: false,
-                    can_overwrite: true,
-                }),
-                false,
-            );
+            let parts = open_parts.len();
+            let mut file_paths = vec![];
+            for mut description in
+                std::iter::once(the_task_description.clone()).chain(open_parts.into_iter().cloned())
+            {
+                description.task.state_log.push(state.clone());
+                // a task that is put aside is not worked on anymore
+                if !matches!(state, State::ToDo(_)) {
+                    stop_interval(&mut description.task, state.at());
+                }
 
-            let fp = file_path.to_string_lossy().into_owned();
+                let file_path = task_dir.join(&description.file_name);
+                let new_file_content = serde_json::to_string_pretty(&description.task)
+                    .map_err(|e| {
+                        Error::FileCouldNotSerializeEntryIntoJson(e, description.file_name)
+                    })?
+                    .into_bytes();
+                effects.add(
+                    EffectKind::WriteToFile(FileWriterOpts {
+                        content: new_file_content,
+                        file_path: file_path.clone(),
+                        can_create: false,
+                        can_overwrite: true,
+                    }),
+                    false,
+                );
+                file_paths.push(file_path.to_string_lossy().into_owned());
 
-            effects.add(git::add(repo_root, &[fp]), false);
+                if let State::Done(at) = state {
+                    // every new instance gets its own second, ids and file names come from it
+                    let done_at = *at + time::Duration::seconds(created);
+                    if let Some(file_path) = write_next_instance(
+                        &mut effects,
+                        task_dir,
+                        &description.task,
+                        done_at,
+                        time_format_descriptor,
+                    )? {
+                        created += 1;
+                        file_paths.push(file_path);
+                    }
+                }
+            }
+
+            effects.add(git::add(repo_root, &file_paths), false);
             effects.add(
                 git::commit(
                     repo_root,
-                    &format!(
-                        "feat: updated task {} to the new state {}",
-                        the_task_description.task.id, state,
-                    ),
+                    &match parts {
+                        0 => format!(
+                            "feat: updated task {} to the new state {}",
+                            the_task_description.task.id, state,
+                        ),
+                        n => format!(
+                            "feat: updated task {} and {n} of its parts to the new state {}",
+                            the_task_description.task.id, state,
+                        ),
+                    },
                 ),
                 false,
             );
@@ -122,15 +537,317 @@ pub fn mark_task_as(
         })
 }
 
+/// Starts working on the task, as long as no other task is being worked on.
+pub fn start_task(
+    task_dir: &Path,
+    tasks_list: &TaskList,
+    task_identifier: i64,
+    repo_root: &str,
+    now: OffsetDateTime,
+) -> Result<EffectMachine> {
+    let the_task_description = tasks_list.find(task_identifier)?;
+    if let Some(active) = tasks_list.active() {
+        return Err(if active.task.id == the_task_description.task.id {
+            Error::TaskIsAlreadyActive(active.task.id)
+        } else {
+            Error::AnotherTaskIsActive(active.task.id)
+        });
+    }
+
+    let mut task = the_task_description.task.clone();
+    task.time_log.push(Interval {
+        start: now,
+        end: None,
+    });
+    write_task(
+        task_dir,
+        the_task_description,
+        &task,
+        repo_root,
+        &format!("feat(tasks): start working on task {}", task.id),
+    )
+}
+
+/// Stops working on the task that is being worked on.
+pub fn stop_task(
+    task_dir: &Path,
+    tasks_list: &TaskList,
+    repo_root: &str,
+    now: OffsetDateTime,
+) -> Result<EffectMachine> {
+    let the_task_description = tasks_list.active().ok_or(Error::NoTaskIsActive)?;
+    let mut task = the_task_description.task.clone();
+    let worked = stop_interval(&mut task, now).unwrap_or_default();
+    let mut effects = write_task(
+        task_dir,
+        the_task_description,
+        &task,
+        repo_root,
+        &format!("feat(tasks): stop working on task {}", task.id),
+    )?;
+    effects.add(
+        EffectKind::PrintToStdOut(format!(
+            "worked on task {} for {}",
+            task.id,
+            tracking::human(worked)
+        )),
+        false,
+    );
+    Ok(effects)
+}
+
+/// Ends the interval that goes on, and gives back how long it was.
+fn stop_interval(task: &mut Task, at: OffsetDateTime) -> Option<time::Duration> {
+    let interval = task.time_log.last_mut().filter(|i| i.end.is_none())?;
+    interval.end = Some(at.max(interval.start));
+    Some(at - interval.start)
+}
+
+/// Writes the task over its file in a commit of its own.
+fn write_task(
+    task_dir: &Path,
+    the_task_description: &TaskDescription,
+    task: &Task,
+    repo_root: &str,
+    message: &str,
+) -> Result<EffectMachine> {
+    let mut effects = EffectMachine::default();
+    let file_path = task_dir.join(&the_task_description.file_name);
+    effects.add(
+        EffectKind::WriteToFile(FileWriterOpts {
+            content: serde_json::to_string_pretty(task)
+                .map_err(|e| {
+                    Error::FileCouldNotSerializeEntryIntoJson(
+                        e,
+                        the_task_description.file_name.clone(),
+                    )
+                })?
+                .into_bytes(),
+            file_path: file_path.clone(),
+            can_create: false,
+            can_overwrite: true,
+        }),
+        false,
+    );
+    effects.add(
+        git::add(repo_root, &[file_path.to_string_lossy().into_owned()]),
+        false,
+    );
+    effects.add(git::commit(repo_root, message), false);
+    effects.add(git::pull(repo_root), false);
+    effects.add(git::push(repo_root), false);
+    Ok(effects)
+}
+
+/// Adds the session to the task, and lets it be known that it is over unless it was interrupted.
+pub fn log_focus_session(
+    task_dir: &Path,
+    the_task_description: &TaskDescription,
+    session: Session,
+    repo_root: &str,
+    config: &focus::Config,
+) -> Result<EffectMachine> {
+    let mut task = the_task_description.task.clone();
+    task.focus_log.push(session);
+    let minutes = (session.end - session.start).whole_minutes();
+    let mut effects = write_task(
+        task_dir,
+        the_task_description,
+        &task,
+        repo_root,
+        &if session.interrupted {
+            format!(
+                "feat(tasks): focus on task {} for {minutes} minutes, interrupted",
+                task.id
+            )
+        } else {
+            format!(
+                "feat(tasks): focus on task {} for {minutes} minutes",
+                task.id
+            )
+        },
+    )?;
+    if !session.interrupted {
+        effects.add(
+            config.notification(&format!("focused on {} for {minutes} minutes", task.title)),
+            true,
+        );
+    }
+    Ok(effects)
+}
+
+/// The tasks focused on today, with how many sessions and minutes each.
+pub fn focus_summary(
+    all_tasks: &TaskList,
+    current_time: OffsetDateTime,
+    format: output::Format,
+) -> Result<EffectMachine> {
+    let mut effects = EffectMachine::default();
+    let summary = focus::summary(all_tasks, current_time.date());
+    if let Some(rendered) = output::render(&summary, format)? {
+        effects.add(EffectKind::PrintToStdOut(rendered), false);
+        return Ok(effects);
+    }
+
+    if summary.is_empty() {
+        effects.add(
+            EffectKind::PrintToStdOut("nothing was focused on today".to_owned()),
+            false,
+        );
+        return Ok(effects);
+    }
+    let minutes: i64 = summary.iter().map(|f| f.minutes).sum();
+    let sessions: usize = summary.iter().map(|f| f.sessions).sum();
+    let mut buf = format!(
+        "{:61}\n",
+        format!(
+            "{} focused today in {sessions} sessions",
+            tracking::human(time::Duration::minutes(minutes))
+        )
+        .bold()
+        .black()
+        .on_bright_blue()
+    );
+    for focused in &summary {
+        buf.push_str(&format!(
+            "\n{}  {} {} {}",
+            format!(
+                "{:>9}",
+                tracking::human(time::Duration::minutes(focused.minutes))
+            )
+            .green(),
+            focused.id.bright_black(),
+            focused.title,
+            format!("🍅 {}", focused.sessions).bright_black(),
+        ));
+        if focused.interrupted > 0 {
+            buf.push_str(&format!(
+                " {}",
+                format!("{} interrupted", focused.interrupted).red()
+            ));
+        }
+    }
+    effects.add(EffectKind::PrintToStdOut(buf), false);
+    Ok(effects)
+}
+
+/// The time tracked over the period around today, per task, project and area.
+pub fn time_report(
+    all_tasks: &TaskList,
+    period: Period,
+    current_time: OffsetDateTime,
+    format: output::Format,
+) -> Result<EffectMachine> {
+    let mut effects = EffectMachine::default();
+    let (from, to) = period.around(current_time.date());
+    let last = to.previous_day().unwrap_or(to);
+    let offset = current_time.offset();
+    let report = tracking::report(
+        all_tasks,
+        from.midnight().assume_offset(offset),
+        to.midnight().assume_offset(offset),
+        current_time,
+    );
+    if let Some(rendered) = output::render(&report, format)? {
+        effects.add(EffectKind::PrintToStdOut(rendered), false);
+        return Ok(effects);
+    }
+
+    if report.is_empty() {
+        effects.add(
+            EffectKind::PrintToStdOut(format!("no time was tracked from {from} to {last}")),
+            false,
+        );
+        return Ok(effects);
+    }
+    let total: i64 = report
+        .iter()
+        .filter(|t| t.by == "task")
+        .map(|t| t.seconds)
+        .sum();
+    let mut buf = format!(
+        "{:61}\n",
+        format!(
+            "{} tracked from {from} to {last}",
+            tracking::human(time::Duration::seconds(total))
+        )
+        .bold()
+        .black()
+        .on_bright_blue()
+    );
+    for by in ["task", "project", "area"] {
+        buf.push_str(&format!("\n{}\n", format!("per {by}:").bold()));
+        for tracked in report.iter().filter(|t| t.by == by) {
+            buf.push_str(&format!(
+                "{}  {}\n",
+                format!(
+                    "{:>9}",
+                    tracking::human(time::Duration::seconds(tracked.seconds))
+                )
+                .green(),
+                tracked.name
+            ));
+        }
+    }
+    effects.add(EffectKind::PrintToStdOut(buf.trim_end().to_owned()), false);
+    Ok(effects)
+}
+
+/// Writes the task that follows a recurring one that was done, and gives back where it was written.
+fn write_next_instance(
+    effects: &mut EffectMachine,
+    task_dir: &Path,
+    task: &Task,
+    done_at: OffsetDateTime,
+    time_format_descriptor: &(impl Formattable + ?Sized),
+) -> Result<Option<String>> {
+    let Some(next) = task.next_instance(done_at) else {
+        return Ok(None);
+    };
+    let file_name = next.to_file_name(time_format_descriptor)?;
+    let file_path = task_dir.join(&file_name);
+    effects.add(
+        EffectKind::WriteToFile(FileWriterOpts {
+            content: serde_json::to_string_pretty(&next)
+                .map_err(|e| Error::FileCouldNotSerializeEntryIntoJson(e, file_name))?
+                .into_bytes(),
+            file_path: file_path.clone(),
+            can_create: true,
+            can_overwrite: false,
+        }),
+        false,
+    );
+    Ok(Some(file_path.to_string_lossy().into_owned()))
+}
+
+/// Tasks that start today, are due today or are overdue. Blocked tasks are left out until every
+/// task they depend on is done, and so are the ones that do not match the query.
 pub fn todays_task(
     all_tasks: TaskList,
     current_date: Date,
     of_project: &Option<String>,
+    query: Option<&Query>,
     current_time: OffsetDateTime,
+    format: output::Format,
     time_format_descriptor: &(impl Formattable + ?Sized),
 ) -> Result<EffectMachine> {
     let mut effects = EffectMachine::default();
 
+    let blocked: Vec<i64> = all_tasks
+        .0
+        .iter()
+        .filter(|t| all_tasks.is_blocked(&t.task))
+        .map(|t| t.task.id)
+        .collect();
+    let all_tasks = TaskList(
+        all_tasks
+            .0
+            .into_iter()
+            .filter(|t| !blocked.contains(&t.task.id))
+            .filter(|t| query.is_none_or(|q| q.matches(&t.task)))
+            .collect(),
+    );
+
     let mut todays_tasks_starting = all_tasks.0.clone();
     todays_tasks_starting.retain(|t| {
         let Some(last) = t.task.state_log.last() else {
@@ -174,6 +891,21 @@ pub fn todays_task(
         let Some(proj) = &of_project else { return true };
         t.task.projects.contains(proj)
     });
+
+    let mut tasks: Vec<&Task> = vec![];
+    for t in todays_tasks_starting
+        .iter()
+        .chain(&todays_tasks_deadline)
+        .chain(&todays_tasks_overdue)
+    {
+        if !tasks.contains(&&t.task) {
+            tasks.push(&t.task);
+        }
+    }
+    if let Some(rendered) = output::render(&tasks, format)? {
+        effects.add(EffectKind::PrintToStdOut(rendered), false);
+        return Ok(effects);
+    }
     effects.add(EffectKind::PrintToStdOut("\n".to_owned()), false);
 
     if !todays_tasks_starting.is_empty() {
@@ -241,6 +973,70 @@ pub fn todays_task(
     Ok(effects)
 }
 
+/// Prints the task, along with the journal entries that mention it and the entries it links to.
+/// Entries of the books that could not be read, like sealed ones, are noted but left out.
+pub fn show_task(
+    tasks_list: &TaskList,
+    task_identifier: i64,
+    books: &[Book],
+    unreadable_entries: usize,
+    current_time: OffsetDateTime,
+    time_format_descriptor: &(impl Formattable + ?Sized),
+) -> Result<EffectMachine> {
+    let mut effects = EffectMachine::default();
+
+    let task = &tasks_list.find(task_identifier)?.task;
+
+    effects.add(
+        EffectKind::PrintToStdOut(
+            task.print_colorful_with_current_duration(current_time, time_format_descriptor)?,
+        ),
+        false,
+    );
+
+    let linked: Vec<String> = task
+        .description
+        .as_deref()
+        .map(links::parse)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|link| match link {
+            Link::Task(id) => format!("→ task {id}"),
+            Link::Entry(name) => format!("→ entry {name}"),
+        })
+        .collect();
+    if !linked.is_empty() {
+        effects.add(EffectKind::PrintToStdOut(linked.join("\n")), false);
+    }
+
+    let mentions: Vec<String> = books
+        .iter()
+        .flat_map(|book| Backlinks::from(book).to_task(task.id).to_vec())
+        .map(|file_name| format!("← entry {file_name}"))
+        .collect();
+    if !mentions.is_empty() {
+        effects.add(
+            EffectKind::PrintToStdOut(format!(
+                "{}\n{}",
+                "mentioned in:".bright_black(),
+                mentions.join("\n")
+            )),
+            false,
+        );
+    }
+    if unreadable_entries > 0 {
+        effects.add(
+            EffectKind::PrintToStdErr(format!(
+                "{unreadable_entries} journal entries could not be read, like encrypted ones, they \
+                 were not looked in"
+            )),
+            false,
+        );
+    }
+
+    Ok(effects)
+}
+
 fn println_ok_or_eprintln(x: Result<String>) -> Effect {
     match x {
         Ok(f) => Effect {
@@ -257,7 +1053,9 @@ pub fn tasks_by_state<F>(
     all_tasks: TaskList,
     task_state_finder: F,
     of_project: &Option<String>,
+    query: Option<&Query>,
     current_time: OffsetDateTime,
+    format: output::Format,
     time_format_descriptor: &(impl Formattable + ?Sized),
 ) -> Result<EffectMachine>
 where
@@ -265,6 +1063,17 @@ where
 {
     let mut effects = EffectMachine::default();
 
+    let notes: Vec<(i64, Vec<String>)> = all_tasks
+        .0
+        .iter()
+        .map(|t| {
+            let notes = [
+                blocked_note(&all_tasks, &t.task),
+                progress_note(&all_tasks, &t.task),
+            ];
+            (t.task.id, notes.into_iter().flatten().collect())
+        })
+        .collect();
     let mut chosen_tasks = all_tasks.0;
     chosen_tasks.retain(|task_description| {
         let Some(last) = task_description.task.state_log.last() else {
@@ -273,12 +1082,21 @@ where
         if !task_state_finder(last) {
             return false;
         };
+        if query.is_some_and(|q| !q.matches(&task_description.task)) {
+            return false;
+        }
         let Some(proj) = &of_project else {
             return true;
         };
         task_description.task.projects.contains(proj)
     });
 
+    let tasks: Vec<&Task> = chosen_tasks.iter().map(|t| &t.task).collect();
+    if let Some(rendered) = output::render(&tasks, format)? {
+        effects.add(EffectKind::PrintToStdOut(rendered), false);
+        return Ok(effects);
+    }
+
     if !chosen_tasks.is_empty() {
         effects.add(
             EffectKind::PrintToStdOut(format!(
@@ -287,14 +1105,30 @@ where
             )),
             false,
         );
-        chosen_tasks
-            .iter()
-            .map(|x| {
+        nested(&chosen_tasks)
+            .into_iter()
+            .map(|(depth, x)| {
                 try_print_colorful_with_current_duration(
                     &x.task,
                     current_time,
                     time_format_descriptor,
                 )
+                .map(|printed| {
+                    let notes = notes
+                        .iter()
+                        .find(|(id, _)| *id == x.task.id)
+                        .map(|(_, notes)| notes.as_slice())
+                        .unwrap_or_default();
+                    std::iter::once(printed.as_str())
+                        .chain(notes.iter().map(String::as_str))
+                        .flat_map(str::lines)
+                        .map(|l| match l {
+                            "" => String::new(),
+                            l => format!("{}{l}", "    ".repeat(depth)),
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
             })
             .map(println_ok_or_eprintln)
             .for_each(|ef| effects.add(ef.effect_kind, ef.forgiving));
@@ -303,6 +1137,120 @@ where
     Ok(effects)
 }
 
+/// The open tasks that are not blocked, most urgent first, with how urgent each one is.
+pub fn next_tasks(
+    all_tasks: &TaskList,
+    count: usize,
+    of_project: &Option<String>,
+    current_time: OffsetDateTime,
+    config: &urgency::Config,
+    format: output::Format,
+    time_format_descriptor: &(impl Formattable + ?Sized),
+) -> Result<EffectMachine> {
+    let mut effects = EffectMachine::default();
+
+    let mut scored: Vec<(f64, &Task)> = all_tasks
+        .0
+        .iter()
+        .map(|t| &t.task)
+        .filter(|t| matches!(t.current_state(), Some(State::ToDo(_))))
+        .filter(|t| !all_tasks.is_blocked(t))
+        .filter(|t| of_project.as_ref().is_none_or(|p| t.projects.contains(p)))
+        // the blocked ones are left out above
+        .map(|t| (urgency::score(t, false, current_time, config), t))
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.truncate(count);
+
+    let tasks: Vec<&Task> = scored.iter().map(|(_, t)| *t).collect();
+    if let Some(rendered) = output::render(&tasks, format)? {
+        effects.add(EffectKind::PrintToStdOut(rendered), false);
+        return Ok(effects);
+    }
+
+    if scored.is_empty() {
+        effects.add(
+            EffectKind::PrintToStdOut("nothing to do next".to_owned()),
+            false,
+        );
+        return Ok(effects);
+    }
+    effects.add(
+        EffectKind::PrintToStdOut(format!("{:61}", "next up:".bold().black().on_bright_blue())),
+        false,
+    );
+    scored
+        .into_iter()
+        .map(|(score, task)| {
+            try_print_colorful_with_current_duration(task, current_time, time_format_descriptor)
+                .map(|printed| {
+                    format!(
+                        "{printed}\n{}",
+                        format!("⚡ urgency {score:.1}").bright_black()
+                    )
+                })
+        })
+        .map(println_ok_or_eprintln)
+        .for_each(|ef| effects.add(ef.effect_kind, ef.forgiving));
+
+    Ok(effects)
+}
+
+/// The tasks with the parts of every task right under it, along with how deep they are. Parts
+/// whose parent is not among the tasks are not nested.
+fn nested(tasks: &[TaskDescription]) -> Vec<(usize, &TaskDescription)> {
+    fn push<'a>(
+        tasks: &'a [TaskDescription],
+        task: &'a TaskDescription,
+        depth: usize,
+        ordered: &mut Vec<(usize, &'a TaskDescription)>,
+    ) {
+        // a hand edited task can still be its own ancestor
+        if ordered.iter().any(|(_, t)| t.task.id == task.task.id) {
+            return;
+        }
+        ordered.push((depth, task));
+        for child in tasks.iter().filter(|t| t.task.parent == Some(task.task.id)) {
+            push(tasks, child, depth + 1, ordered);
+        }
+    }
+
+    let mut ordered = vec![];
+    let roots = tasks.iter().filter(|t| {
+        t.task
+            .parent
+            .is_none_or(|p| !tasks.iter().any(|o| o.task.id == p))
+    });
+    // tasks that are their own ancestors have no root, they are listed last
+    for task in roots.chain(tasks) {
+        push(tasks, task, 0, &mut ordered);
+    }
+    ordered
+}
+
+/// How many parts of a task are done.
+fn progress_note(all_tasks: &TaskList, task: &Task) -> Option<String> {
+    all_tasks
+        .progress(task.id)
+        .map(|(done, total)| format!("{}", format!("▤ {done}/{total} done").bright_black()))
+}
+
+/// Which tasks a blocked task is waiting for.
+fn blocked_note(all_tasks: &TaskList, task: &Task) -> Option<String> {
+    let open = all_tasks.open_dependencies(task);
+    (!open.is_empty()).then(|| {
+        format!(
+            "{} {}",
+            "⊘ blocked by".red(),
+            open.iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+                .red()
+        )
+    })
+}
+
 fn try_print_colorful_with_current_duration(
     x: &Task,
     current_time: OffsetDateTime,
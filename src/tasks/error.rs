@@ -1,4 +1,4 @@
-use crate::git;
+use crate::{git, migration, output, timeutils};
 
 use super::tasklist::TaskDescription;
 
@@ -36,8 +36,48 @@ pub enum Error {
     FileNameHasInvalidCharacters,
     #[error("the path is not a file")]
     IsNotAFile,
-    #[error("something went wrong in parsing line: {0}")]
-    ParsingLineFailed(Box<String>),
+    #[error("line {0}, column {1} of the bulk actions: expected {2}")]
+    ParsingLineFailed(usize, usize, String),
+    #[error("column {0} of the query: expected {1}")]
+    QueryCouldNotBeParsed(usize, String),
+    #[error("could not read task {1}: {0}")]
+    CouldNotMigrateTask(migration::Error, String),
+    #[error("migrating the tasks failed: {0}")]
+    MigrationFailed(#[from] migration::Error),
+    #[error("line {0} of the bulk actions does not match a listed task")]
+    BulkLineDoesNotMatchATask(u64),
+    #[error("could not parse the date: {0}")]
+    DateCouldNotBeParsed(timeutils::Error),
+    #[error("the title of the task is empty")]
+    TaskTitleIsEmpty,
+    #[error("the task starts on {0}, after its deadline on {1}")]
+    StartIsAfterDeadline(time::Date, time::Date),
+    #[error("could not parse the task sheet: {0}")]
+    TaskSheetCouldNotBeParsed(String),
+    #[error("unknown field in the task sheet: {0}")]
+    UnknownTaskSheetField(String),
+    #[error("{0} is not an area, one of work, home or personal")]
+    InvalidArea(String),
+    #[error("{0} is not a priority, one of low, medium or high")]
+    InvalidPriority(String),
+    #[error("task {0} is being worked on, stop it before starting another one")]
+    AnotherTaskIsActive(i64),
+    #[error("task {0} is already being worked on")]
+    TaskIsAlreadyActive(i64),
+    #[error("no task is being worked on")]
+    NoTaskIsActive,
+    #[error("the countdown could not run: {0}")]
+    CountdownCouldNotRun(std::io::Error),
+    #[error("task {0} can not depend on task {1}, task {1} already depends on it")]
+    DependencyCycle(i64, i64),
+    #[error("task {0} does not depend on task {1}")]
+    NotADependency(i64, i64),
+    #[error("task {0} still has {1} open parts, close them first or pass --recursive")]
+    TaskHasOpenParts(i64, usize),
+    #[error("could not parse the recurrence rule: {0}")]
+    RecurrenceCouldNotBeParsed(String),
+    #[error("could not write the output: {0}")]
+    OutputFailed(#[from] output::Error),
 }
 #[cfg(test)]
 mod testing {
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use time::{Date, Duration, OffsetDateTime};
+
+use crate::effects::EffectKind;
+use crate::output::Row;
+
+use super::{tasklist::TaskList, Error};
+
+/// A stretch of focused work on a task, timed by `tsk focus`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Session {
+    #[serde(with = "time::serde::rfc3339")]
+    pub start: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub end: OffsetDateTime,
+    /// How long the session was meant to last
+    pub planned_minutes: u16,
+    /// Stopped before the time was up
+    pub interrupted: bool,
+}
+
+/// How focus sessions end, besides printing where they stand.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Config {
+    /// Run at the end of every session and break instead of ringing the terminal bell, the
+    /// message is given in `TSK_FOCUS_MESSAGE`
+    pub notify: Option<Vec<String>>,
+}
+
+impl Config {
+    /// Rings the bell, or runs the notification command.
+    #[must_use]
+    pub fn notification(&self, message: &str) -> EffectKind {
+        match self.notify.as_deref() {
+            Some([command, args @ ..]) => EffectKind::RunExternalCommand(
+                command.clone(),
+                args.to_vec(),
+                HashMap::from([("TSK_FOCUS_MESSAGE".to_owned(), message.to_owned())]),
+            ),
+            _ => EffectKind::PrintToStdOut(format!("\u{7}{message}")),
+        }
+    }
+}
+
+/// How a countdown came to an end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Ending {
+    Finished,
+    /// By ctrl-c
+    Interrupted,
+}
+
+/// Counts down in the terminal, rewriting a single line every second, until the time is up or
+/// ctrl-c is pressed.
+pub fn countdown(label: &str, length: Duration) -> Result<Ending, Error> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(Error::CountdownCouldNotRun)?;
+    runtime.block_on(async {
+        let total = u64::try_from(length.whole_seconds()).unwrap_or_default();
+        let interrupted = tokio::signal::ctrl_c();
+        tokio::pin!(interrupted);
+        let mut ticks = tokio::time::interval(std::time::Duration::from_secs(1));
+        for elapsed in 0..total {
+            tokio::select! {
+                _ = ticks.tick() => {}
+                _ = &mut interrupted => {
+                    eprintln!();
+                    return Ok(Ending::Interrupted);
+                }
+            }
+            let left = total - elapsed;
+            eprint!("\r{label} {:02}:{:02} ", left / 60, left % 60);
+            std::io::stderr()
+                .flush()
+                .map_err(Error::CountdownCouldNotRun)?;
+        }
+        tokio::select! {
+            _ = ticks.tick() => {}
+            _ = &mut interrupted => {
+                eprintln!();
+                return Ok(Ending::Interrupted);
+            }
+        }
+        eprintln!("\r{label} 00:00 ");
+        Ok(Ending::Finished)
+    })
+}
+
+/// The focus sessions of one task on a day.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Focused {
+    pub id: i64,
+    pub title: String,
+    pub sessions: usize,
+    pub interrupted: usize,
+    pub minutes: i64,
+}
+
+impl Row for Focused {
+    fn columns() -> Vec<&'static str> {
+        vec!["id", "title", "sessions", "interrupted", "minutes"]
+    }
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.title.clone(),
+            self.sessions.to_string(),
+            self.interrupted.to_string(),
+            self.minutes.to_string(),
+        ]
+    }
+}
+
+/// The tasks that were focused on during the day, the most focused on first.
+#[must_use]
+pub fn summary(tasks: &TaskList, day: Date) -> Vec<Focused> {
+    let mut focused: Vec<Focused> = tasks
+        .0
+        .iter()
+        .map(|t| &t.task)
+        .filter_map(|task| {
+            let sessions: Vec<&Session> = task
+                .focus_log
+                .iter()
+                .filter(|s| s.start.date() == day)
+                .collect();
+            (!sessions.is_empty()).then(|| Focused {
+                id: task.id,
+                title: task.title.clone(),
+                sessions: sessions.len(),
+                interrupted: sessions.iter().filter(|s| s.interrupted).count(),
+                minutes: sessions
+                    .iter()
+                    .map(|s| (s.end - s.start).whole_minutes())
+                    .sum(),
+            })
+        })
+        .collect();
+    focused.sort_by(|a, b| b.minutes.cmp(&a.minutes).then_with(|| a.id.cmp(&b.id)));
+    focused
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::tasks::{task::Task, tasklist::TaskDescription};
+    use rstest::*;
+
+    const fn is_normal<T: Sized + Send + Sync + Unpin>() {}
+
+    #[test]
+    const fn normal_types() {
+        is_normal::<Session>();
+        is_normal::<Config>();
+        is_normal::<Focused>();
+    }
+
+    fn at(minutes: i64) -> OffsetDateTime {
+        OffsetDateTime::UNIX_EPOCH + Duration::minutes(minutes)
+    }
+
+    fn session(start: i64, end: i64, interrupted: bool) -> Session {
+        Session {
+            start: at(start),
+            end: at(end),
+            planned_minutes: 25,
+            interrupted,
+        }
+    }
+
+    fn task(id: i64, focus_log: Vec<Session>) -> TaskDescription {
+        TaskDescription {
+            task: Task {
+                id,
+                focus_log,
+                ..Task::new(format!("task {id}"), at(0))
+            },
+            file_name: format!("{id}.json"),
+        }
+    }
+
+    #[rstest]
+    fn summary_of_the_day() {
+        let tasks = TaskList(vec![
+            task(1, vec![session(0, 25, false)]),
+            task(
+                2,
+                vec![
+                    session(30, 55, false),
+                    session(60, 70, true),
+                    // the next day
+                    session(1500, 1525, false),
+                ],
+            ),
+            task(3, vec![]),
+        ]);
+        assert_eq!(
+            summary(&tasks, at(0).date()),
+            [
+                Focused {
+                    id: 2,
+                    title: "task 2".to_owned(),
+                    sessions: 2,
+                    interrupted: 1,
+                    minutes: 35,
+                },
+                Focused {
+                    id: 1,
+                    title: "task 1".to_owned(),
+                    sessions: 1,
+                    interrupted: 0,
+                    minutes: 25,
+                },
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::bell(None, "PrintToStdOut")]
+    #[case::command(Some(vec!["notify-send".to_owned(), "tsk".to_owned()]), "RunExternalCommand")]
+    fn notifications(#[case] notify: Option<Vec<String>>, #[case] kind: &str) {
+        let notification = Config { notify }.notification("done");
+        assert!(format!("{notification:?}").starts_with(kind));
+    }
+}
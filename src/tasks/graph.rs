@@ -0,0 +1,156 @@
+use super::{
+    task::{State, Task},
+    tasklist::TaskList,
+};
+
+/// The open tasks that no other open task depends on, but that depend on something themselves.
+fn roots(tasks: &TaskList) -> Vec<&Task> {
+    let open: Vec<&Task> = tasks
+        .0
+        .iter()
+        .map(|t| &t.task)
+        .filter(|t| matches!(t.current_state(), Some(State::ToDo(_) | State::Backlog(_))))
+        .collect();
+    open.iter()
+        .copied()
+        .filter(|t| !t.depends_on.is_empty())
+        .filter(|t| !open.iter().any(|o| o.depends_on.contains(&t.id)))
+        .collect()
+}
+
+/// Every open task that depends on others, with the tasks it depends on nested under it.
+///
+/// ```text
+/// ☐ TODO 3 ship it
+/// ├── ☑ DONE 1 write it
+/// └── ☐ TODO 2 test it
+/// ```
+#[must_use]
+pub fn tree(tasks: &TaskList) -> String {
+    let mut buf = String::new();
+    for root in roots(tasks) {
+        buf.push_str(&label(root));
+        buf.push('\n');
+        branches(tasks, root, "", &mut vec![root.id], &mut buf);
+    }
+    buf.trim_end().to_owned()
+}
+
+fn branches(tasks: &TaskList, task: &Task, prefix: &str, path: &mut Vec<i64>, buf: &mut String) {
+    for (i, id) in task.depends_on.iter().enumerate() {
+        let last = i + 1 == task.depends_on.len();
+        buf.push_str(prefix);
+        buf.push_str(if last { "└── " } else { "├── " });
+        let child_prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+        match tasks.get(*id) {
+            // a hand edited task can still make a cycle
+            Some(_) if path.contains(id) => buf.push_str(&format!("↻ {id}\n")),
+            Some(dependency) => {
+                buf.push_str(&label(&dependency.task));
+                buf.push('\n');
+                path.push(*id);
+                branches(tasks, &dependency.task, &child_prefix, path, buf);
+                path.pop();
+            }
+            None => buf.push_str(&format!("? {id} is missing\n")),
+        }
+    }
+}
+
+fn label(task: &Task) -> String {
+    format!(
+        "{} {} {}",
+        task.current_state()
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+        task.id,
+        task.title
+    )
+}
+
+/// The same tasks as [`tree`] in the DOT language, with an edge from every task to each task it
+/// depends on.
+#[must_use]
+pub fn dot(tasks: &TaskList) -> String {
+    let mut nodes: Vec<&Task> = vec![];
+    let mut to_visit = roots(tasks);
+    while let Some(task) = to_visit.pop() {
+        if nodes.iter().any(|n| n.id == task.id) {
+            continue;
+        }
+        nodes.push(task);
+        to_visit.extend(
+            task.depends_on
+                .iter()
+                .filter_map(|id| tasks.get(*id))
+                .map(|d| &d.task),
+        );
+    }
+    nodes.sort_by_key(|n| n.id);
+
+    let mut buf = String::from("digraph tasks {\n");
+    for node in &nodes {
+        buf.push_str(&format!(
+            "    \"{}\" [label=\"{}\\n{}\"];\n",
+            node.id,
+            node.title.replace('\\', "\\\\").replace('"', "\\\""),
+            node.current_state().map(State::name).unwrap_or_default(),
+        ));
+    }
+    for node in &nodes {
+        for id in &node.depends_on {
+            buf.push_str(&format!("    \"{}\" -> \"{id}\";\n", node.id));
+        }
+    }
+    buf.push('}');
+    buf
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::tasks::tasklist::TaskDescription;
+    use rstest::*;
+
+    fn task(id: i64, title: &str, depends_on: &[i64], done: bool) -> TaskDescription {
+        let now = time::OffsetDateTime::UNIX_EPOCH;
+        TaskDescription {
+            task: Task {
+                id,
+                state_log: if done {
+                    vec![State::Done(now)]
+                } else {
+                    vec![State::ToDo(now)]
+                },
+                depends_on: depends_on.to_vec(),
+                ..Task::new(title.to_owned(), now)
+            },
+            file_name: format!("{id}.json"),
+        }
+    }
+
+    fn tasks() -> TaskList {
+        TaskList(vec![
+            task(1, "write it", &[], true),
+            task(2, "test it", &[1], false),
+            task(3, "ship it", &[1, 2, 9], false),
+            task(4, "unrelated", &[], false),
+        ])
+    }
+
+    #[rstest]
+    fn tree_of_dependencies() {
+        assert_eq!(
+            tree(&tasks()),
+            "☐ TODO 3 ship it\n├── ☑ DONE 1 write it\n├── ☐ TODO 2 test it\n│   └── ☑ DONE 1 write it\n└── ? 9 is missing"
+        );
+    }
+
+    #[rstest]
+    fn dot_of_dependencies() {
+        assert_eq!(
+            dot(&tasks()),
+            "digraph tasks {\n    \"1\" [label=\"write it\\ndone\"];\n    \"2\" [label=\"test it\\ntodo\"];\n    \"3\" [label=\"ship it\\ntodo\"];\n    \"2\" -> \"1\";\n    \"3\" -> \"1\";\n    \"3\" -> \"2\";\n    \"3\" -> \"9\";\n}"
+        );
+    }
+}
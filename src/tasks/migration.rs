@@ -0,0 +1,119 @@
+use serde_json::Value;
+
+use crate::migration::{Error, Registry};
+
+/// Every upgrade that task documents went through.
+pub const REGISTRY: Registry = Registry {
+    kind: "task",
+    steps: &[
+        v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6, v6_to_v7, v7_to_v8,
+    ],
+};
+
+/// The version of the task documents that this build writes.
+pub const CURRENT_VERSION: u32 = REGISTRY.current_version();
+
+/// Only the version field was introduced, the rest of the task stayed the same.
+#[allow(clippy::unnecessary_wraps)] // every step has to have the same signature
+const fn v1_to_v2(document: Value) -> Result<Value, Error> {
+    Ok(document)
+}
+
+/// Adds `depends_on`, the ids of the tasks that have to be done first, as an empty list.
+fn v2_to_v3(mut document: Value) -> Result<Value, Error> {
+    document
+        .as_object_mut()
+        .ok_or(Error::DocumentIsNotAnObject("task"))?
+        .entry("depends_on")
+        .or_insert_with(|| Value::Array(vec![]));
+    Ok(document)
+}
+
+/// Adds `parent`, the id of the task this one is a part of, as `null`.
+fn v3_to_v4(mut document: Value) -> Result<Value, Error> {
+    document
+        .as_object_mut()
+        .ok_or(Error::DocumentIsNotAnObject("task"))?
+        .entry("parent")
+        .or_insert(Value::Null);
+    Ok(document)
+}
+
+/// Adds `recurrence`, when the task comes back once it is done, as `null`.
+fn v4_to_v5(mut document: Value) -> Result<Value, Error> {
+    document
+        .as_object_mut()
+        .ok_or(Error::DocumentIsNotAnObject("task"))?
+        .entry("recurrence")
+        .or_insert(Value::Null);
+    Ok(document)
+}
+
+/// Adds `priority`, one of `Low`, `Medium` or `High`, as `null`.
+fn v5_to_v6(mut document: Value) -> Result<Value, Error> {
+    document
+        .as_object_mut()
+        .ok_or(Error::DocumentIsNotAnObject("task"))?
+        .entry("priority")
+        .or_insert(Value::Null);
+    Ok(document)
+}
+
+/// Adds `time_log`, the intervals the task was worked on with their rfc3339 `start` and `end`,
+/// as an empty list.
+fn v6_to_v7(mut document: Value) -> Result<Value, Error> {
+    document
+        .as_object_mut()
+        .ok_or(Error::DocumentIsNotAnObject("task"))?
+        .entry("time_log")
+        .or_insert(Value::Array(vec![]));
+    Ok(document)
+}
+
+/// Adds `focus_log`, the focus sessions with their `start`, `end`, `planned_minutes` and whether
+/// they were `interrupted`, as an empty list.
+fn v7_to_v8(mut document: Value) -> Result<Value, Error> {
+    document
+        .as_object_mut()
+        .ok_or(Error::DocumentIsNotAnObject("task"))?
+        .entry("focus_log")
+        .or_insert(Value::Array(vec![]));
+    Ok(document)
+}
+
+#[cfg(test)]
+mod testing {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::migration::version_of;
+    use crate::tasks::task::Task;
+    use rstest::*;
+    use serde_json::json;
+
+    #[rstest]
+    fn v1_is_readable() {
+        let old = json!({
+            "id": 1_697_000_000_i64,
+            "time_created": "2023-10-11T04:53:20Z",
+            "state_log": [{ "ToDo": "2023-10-11T04:53:20Z" }],
+            "title": "old task",
+            "description": null,
+            "area": "Work",
+            "people": [],
+            "projects": ["prmait"],
+            "start": null,
+            "end": null,
+        });
+        let new = REGISTRY.migrate(old).unwrap();
+        assert_eq!(version_of(&new), CURRENT_VERSION);
+        let task: Task = serde_json::from_value(new).unwrap();
+        assert_eq!(task.title, "old task");
+        assert!(task.depends_on.is_empty());
+        assert_eq!(task.parent, None);
+        assert_eq!(task.recurrence, None);
+        assert_eq!(task.priority, None);
+        assert!(task.time_log.is_empty());
+        assert!(task.focus_log.is_empty());
+    }
+}
@@ -0,0 +1,385 @@
+use std::str::FromStr;
+
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, tag_no_case, take_till1},
+    character::complete::{char, multispace0, multispace1},
+    combinator::{all_consuming, cut, map, map_res, peek, value},
+    error::{context, ContextError, FromExternalError, ParseError, VerboseError},
+    multi::fold_many0,
+    sequence::{delimited, preceded, terminated, tuple},
+    Finish, IResult,
+};
+use time::{Date, UtcOffset};
+
+use super::{
+    bulk::failed_at,
+    task::{Area, AreaParsingError, State, Task},
+    Error,
+};
+use crate::timeutils;
+
+/// Which tasks to list, written like `state = todo and (project = prmait or deadline < 1w)`.
+///
+/// - `state = todo`, one of todo, done, backlog or abandoned
+/// - `project = name`, `person = name` and `area = work` match one of the values of the task
+/// - `title ~ text` and `description ~ text` match a part of them, whatever the case
+/// - `deadline < 1w` and `start >= 2023-10-01` compare with a date in the `timeutils` syntax,
+///   with one of `<`, `<=`, `=`, `>=` or `>`, and `deadline = none` matches tasks without one
+/// - `not`, `and` and `or` combine them, in that order of precedence, and parentheses group them
+///
+/// Values with white space or parentheses in them are written in double quotes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Query {
+    And(Box<Self>, Box<Self>),
+    Or(Box<Self>, Box<Self>),
+    Not(Box<Self>),
+    /// The plain name of the state, like `todo`
+    State(String),
+    Project(String),
+    Person(String),
+    Area(Area),
+    Title(String),
+    Description(String),
+    /// `None` matches the tasks without that date
+    Date(DateField, Comparison, Option<Date>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DateField {
+    Deadline,
+    Start,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Comparison {
+    Before,
+    BeforeOrOn,
+    On,
+    OnOrAfter,
+    After,
+}
+
+impl Query {
+    /// Relative dates are resolved when the query is parsed, from today at the offset.
+    pub fn parse(input: &str, offset: UtcOffset) -> Result<Self, Error> {
+        let (_, query) = all_consuming(delimited(
+            multispace0,
+            |i| expression::<VerboseError<&str>>(i, offset),
+            multispace0,
+        ))(input)
+        .finish()
+        .map_err(|e| {
+            let (column, expected) = failed_at(input, &e, "a condition");
+            Error::QueryCouldNotBeParsed(column, expected)
+        })?;
+        Ok(query)
+    }
+
+    #[must_use]
+    pub fn matches(&self, task: &Task) -> bool {
+        match self {
+            Self::And(left, right) => left.matches(task) && right.matches(task),
+            Self::Or(left, right) => left.matches(task) || right.matches(task),
+            Self::Not(query) => !query.matches(task),
+            Self::State(name) => task.current_state().map(State::name) == Some(name.as_str()),
+            Self::Project(project) => task.projects.contains(project),
+            Self::Person(person) => task.people.contains(person),
+            Self::Area(area) => task.area.as_ref() == Some(area),
+            Self::Title(text) => contains(&task.title, text),
+            Self::Description(text) => task
+                .description
+                .as_deref()
+                .is_some_and(|d| contains(d, text)),
+            Self::Date(field, comparison, date) => {
+                let task_date = match field {
+                    DateField::Deadline => task.end,
+                    DateField::Start => task.start,
+                };
+                match (task_date, date) {
+                    (None, None) => true,
+                    (Some(task_date), Some(date)) => match comparison {
+                        Comparison::Before => task_date < *date,
+                        Comparison::BeforeOrOn => task_date <= *date,
+                        Comparison::On => task_date == *date,
+                        Comparison::OnOrAfter => task_date >= *date,
+                        Comparison::After => task_date > *date,
+                    },
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+fn contains(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+fn expression<'a, E>(input: &'a str, offset: UtcOffset) -> IResult<&'a str, Query, E>
+where
+    E: ParseError<&'a str>
+        + ContextError<&'a str>
+        + FromExternalError<&'a str, timeutils::Error>
+        + FromExternalError<&'a str, AreaParsingError>,
+{
+    let (input, first) = conjunction(input, offset)?;
+    fold_many0(
+        preceded(keyword("or"), |i| conjunction(i, offset)),
+        move || first.clone(),
+        |left, right| Query::Or(Box::new(left), Box::new(right)),
+    )(input)
+}
+
+fn conjunction<'a, E>(input: &'a str, offset: UtcOffset) -> IResult<&'a str, Query, E>
+where
+    E: ParseError<&'a str>
+        + ContextError<&'a str>
+        + FromExternalError<&'a str, timeutils::Error>
+        + FromExternalError<&'a str, AreaParsingError>,
+{
+    let (input, first) = negation(input, offset)?;
+    fold_many0(
+        preceded(keyword("and"), |i| negation(i, offset)),
+        move || first.clone(),
+        |left, right| Query::And(Box::new(left), Box::new(right)),
+    )(input)
+}
+
+/// `and` and `or` between white space.
+fn keyword<'a, E>(word: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str, E>
+where
+    E: ParseError<&'a str>,
+{
+    delimited(multispace1, tag_no_case(word), multispace1)
+}
+
+fn negation<'a, E>(input: &'a str, offset: UtcOffset) -> IResult<&'a str, Query, E>
+where
+    E: ParseError<&'a str>
+        + ContextError<&'a str>
+        + FromExternalError<&'a str, timeutils::Error>
+        + FromExternalError<&'a str, AreaParsingError>,
+{
+    alt((
+        map(
+            preceded(
+                terminated(tag_no_case("not"), alt((multispace1, peek(tag("("))))),
+                |i| negation(i, offset),
+            ),
+            |query| Query::Not(Box::new(query)),
+        ),
+        delimited(
+            terminated(char('('), multispace0),
+            |i| expression(i, offset),
+            preceded(multispace0, context("closing parenthesis", cut(char(')')))),
+        ),
+        |i| condition(i, offset),
+    ))(input)
+}
+
+fn condition<'a, E>(input: &'a str, offset: UtcOffset) -> IResult<&'a str, Query, E>
+where
+    E: ParseError<&'a str>
+        + ContextError<&'a str>
+        + FromExternalError<&'a str, timeutils::Error>
+        + FromExternalError<&'a str, AreaParsingError>,
+{
+    let equals = || delimited(multispace0, char('='), multispace0);
+    let has = || delimited(multispace0, char('~'), multispace0);
+    context(
+        "a condition, like state = todo or deadline < 1w",
+        alt((
+            preceded(
+                tuple((tag_no_case("state"), equals())),
+                cut(context(
+                    "state, one of todo, done, backlog or abandoned",
+                    map(
+                        alt((
+                            tag_no_case("todo"),
+                            tag_no_case("done"),
+                            tag_no_case("backlog"),
+                            tag_no_case("abandoned"),
+                        )),
+                        |s: &str| Query::State(s.to_lowercase()),
+                    ),
+                )),
+            ),
+            preceded(
+                tuple((tag_no_case("project"), equals())),
+                cut(context("project", map(text, Query::Project))),
+            ),
+            preceded(
+                tuple((tag_no_case("person"), equals())),
+                cut(context("person", map(text, Query::Person))),
+            ),
+            preceded(
+                tuple((tag_no_case("area"), equals())),
+                cut(context(
+                    "area, one of work, home or personal",
+                    map_res(text, |a| Area::from_str(&a).map(Query::Area)),
+                )),
+            ),
+            preceded(
+                tuple((tag_no_case("title"), has())),
+                cut(context("title", map(text, Query::Title))),
+            ),
+            preceded(
+                tuple((tag_no_case("description"), has())),
+                cut(context("description", map(text, Query::Description))),
+            ),
+            |i| date_condition(i, offset),
+        )),
+    )(input)
+}
+
+fn date_condition<'a, E>(input: &'a str, offset: UtcOffset) -> IResult<&'a str, Query, E>
+where
+    E: ParseError<&'a str> + ContextError<&'a str> + FromExternalError<&'a str, timeutils::Error>,
+{
+    let (input, field) = alt((
+        value(DateField::Deadline, tag_no_case("deadline")),
+        value(DateField::Deadline, tag_no_case("due")),
+        value(DateField::Start, tag_no_case("start")),
+    ))(input)?;
+    let (input, comparison) = delimited(
+        multispace0,
+        context(
+            "comparison, one of <, <=, =, >= or >",
+            cut(alt((
+                value(Comparison::BeforeOrOn, tag("<=")),
+                value(Comparison::OnOrAfter, tag(">=")),
+                value(Comparison::Before, tag("<")),
+                value(Comparison::After, tag(">")),
+                value(Comparison::On, tag("=")),
+            ))),
+        ),
+        multispace0,
+    )(input)?;
+    let (input, date) = cut(context(
+        "date, or none",
+        alt((
+            map_res(tag_no_case("none"), |_| match comparison {
+                Comparison::On => Ok(None),
+                _ => Err(timeutils::Error::CouldNotFindAnyPattern),
+            }),
+            map_res(word, |d| timeutils::parse_date(d, offset).map(Some)),
+        )),
+    ))(input)?;
+    Ok((input, Query::Date(field, comparison, date)))
+}
+
+/// A word, or anything between double quotes.
+fn text<'a, E>(input: &'a str) -> IResult<&'a str, String, E>
+where
+    E: ParseError<&'a str>,
+{
+    map(
+        alt((
+            delimited(char('"'), take_till1(|c| c == '"'), char('"')),
+            word,
+        )),
+        ToOwned::to_owned,
+    )(input)
+}
+
+fn word<'a, E>(input: &'a str) -> IResult<&'a str, &'a str, E>
+where
+    E: ParseError<&'a str>,
+{
+    take_till1(|c: char| c.is_whitespace() || c == '(' || c == ')')(input)
+}
+
+#[cfg(test)]
+mod testing {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use rstest::*;
+    use time::{Month, OffsetDateTime};
+
+    const fn is_normal<T: Sized + Send + Sync + Unpin>() {}
+
+    #[test]
+    const fn normal_types() {
+        is_normal::<Query>();
+    }
+
+    fn date(year: i32, month: u8, day: u8) -> Date {
+        Date::from_calendar_date(year, Month::try_from(month).unwrap(), day).unwrap()
+    }
+
+    fn task() -> Task {
+        let now = OffsetDateTime::UNIX_EPOCH;
+        Task {
+            description: Some("before the fifth".to_owned()),
+            area: Some(Area::Home),
+            people: vec!["alice".to_owned()],
+            projects: vec!["flat".to_owned()],
+            end: Some(date(2023, 10, 5)),
+            ..Task::new("Pay the rent".to_owned(), now)
+        }
+    }
+
+    #[rstest]
+    #[case::state("state = todo", true)]
+    #[case::other_state("state=done", false)]
+    #[case::project("project = flat and person = alice", true)]
+    #[case::area("area = work or area = home", true)]
+    #[case::title("title ~ rent", true)]
+    #[case::quoted_title("title ~ \"pay the\"", true)]
+    #[case::description("description ~ sixth", false)]
+    #[case::not("not project = flat", false)]
+    #[case::not_grouped("not(state = done or state = abandoned)", true)]
+    #[case::precedence("state = done and project = flat or person = alice", true)]
+    #[case::grouped("state = done and (project = flat or person = alice)", false)]
+    #[case::deadline_before("deadline < 2023-10-06", true)]
+    #[case::deadline_on("due = 2023-10-05", true)]
+    #[case::deadline_after("deadline > 2023-10-05", false)]
+    #[case::no_start("start = none", true)]
+    #[case::no_deadline("deadline = none", false)]
+    #[case::no_start_compared("start < 2023-10-06", false)]
+    fn matches_the_task(#[case] input: &str, #[case] expected: bool) {
+        let query = Query::parse(input, UtcOffset::UTC).unwrap();
+        assert_eq!(query.matches(&task()), expected, "{query:?}");
+    }
+
+    #[rstest]
+    fn relative_dates() {
+        let query = Query::parse("deadline < 1w", UtcOffset::UTC).unwrap();
+        assert_eq!(
+            query,
+            Query::Date(
+                DateField::Deadline,
+                Comparison::Before,
+                Some(timeutils::day_from_today(UtcOffset::UTC, 7))
+            )
+        );
+    }
+
+    #[rstest]
+    #[case::unknown_field(
+        "colour = red",
+        "column 1 of the query: expected a condition, like state = todo or deadline < 1w"
+    )]
+    #[case::unknown_state(
+        "state = doing",
+        "column 9 of the query: expected state, one of todo, done, backlog or abandoned"
+    )]
+    #[case::unknown_area(
+        "area = garden",
+        "column 8 of the query: expected area, one of work, home or personal"
+    )]
+    #[case::bad_date("deadline < someday", "column 12 of the query: expected date, or none")]
+    #[case::none_compared("deadline < none", "column 12 of the query: expected date, or none")]
+    #[case::unclosed(
+        "(state = todo",
+        "column 14 of the query: expected closing parenthesis"
+    )]
+    fn unhappy_queries(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(
+            Query::parse(input, UtcOffset::UTC).unwrap_err().to_string(),
+            expected
+        );
+    }
+}
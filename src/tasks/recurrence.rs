@@ -0,0 +1,317 @@
+use std::{fmt::Display, str::FromStr};
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag_no_case,
+    character::complete::{char, space0, space1},
+    combinator::{all_consuming, map, opt, value, verify},
+    error::{context, ContextError, ParseError},
+    multi::separated_list1,
+    sequence::{delimited, preceded, terminated, tuple},
+    Parser,
+};
+use time::{Date, Duration, Month, Weekday};
+
+use super::Error;
+
+/// When a task comes back once it is done.
+///
+/// Written as `daily`, `weekly on mon, fri`, `monthly on 1` or `every 10 days after done`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Recurrence {
+    Daily,
+    /// On each of the days of the week
+    Weekly(Vec<Weekday>),
+    /// On that day of every month, or the last day of the months that are shorter
+    Monthly(u8),
+    /// That many days after the task was done, whatever its dates were
+    AfterDone(u16),
+}
+
+impl Recurrence {
+    /// The date the next instance is due, given the date the current one was due and the date it
+    /// was done on. Scheduled instances that were missed are skipped, the next one is always after
+    /// both dates.
+    #[must_use]
+    pub fn next(&self, due: Date, done: Date) -> Date {
+        let after = due.max(done);
+        let mut next = after.saturating_add(Duration::DAY);
+        match self {
+            Self::Daily => next,
+            Self::Weekly(days) if days.is_empty() => next,
+            Self::Weekly(days) => {
+                while !days.contains(&next.weekday()) {
+                    next = next.saturating_add(Duration::DAY);
+                }
+                next
+            }
+            Self::Monthly(day) => {
+                let (mut year, mut month) = (after.year(), after.month());
+                loop {
+                    let candidate =
+                        Date::from_calendar_date(year, month, (*day).min(month.length(year)));
+                    if let Ok(candidate) = candidate {
+                        if candidate > after {
+                            return candidate;
+                        }
+                    }
+                    if month == Month::December {
+                        year += 1;
+                    }
+                    month = month.next();
+                }
+            }
+            Self::AfterDone(days) => done.saturating_add(Duration::days(i64::from(*days))),
+        }
+    }
+
+    /// `Weekday` has no order of its own.
+    fn sort_key(&self) -> (u8, Vec<u8>, u16) {
+        match self {
+            Self::Daily => (0, vec![], 0),
+            Self::Weekly(days) => (
+                1,
+                days.iter().map(|d| d.number_days_from_monday()).collect(),
+                0,
+            ),
+            Self::Monthly(day) => (2, vec![], u16::from(*day)),
+            Self::AfterDone(days) => (3, vec![], *days),
+        }
+    }
+}
+
+impl PartialOrd for Recurrence {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Recurrence {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Daily => write!(f, "daily"),
+            Self::Weekly(days) => write!(
+                f,
+                "weekly on {}",
+                days.iter()
+                    .map(|d| weekday_name(*d))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Self::Monthly(day) => write!(f, "monthly on {day}"),
+            Self::AfterDone(days) => write!(f, "every {days} days after done"),
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lowered = s.trim().to_lowercase();
+        let (_, recurrence) = recurrence_parser::<nom::error::Error<_>>(&lowered)
+            .map_err(|e| Error::RecurrenceCouldNotBeParsed(e.to_string()))?;
+        Ok(recurrence)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Recurrence {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Recurrence {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rule = String::deserialize(deserializer)?;
+        rule.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+const fn weekday_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Monday => "mon",
+        Weekday::Tuesday => "tue",
+        Weekday::Wednesday => "wed",
+        Weekday::Thursday => "thu",
+        Weekday::Friday => "fri",
+        Weekday::Saturday => "sat",
+        Weekday::Sunday => "sun",
+    }
+}
+
+fn recurrence_parser<'a, E>(content: &'a str) -> Result<(&'a str, Recurrence), nom::Err<E>>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+{
+    all_consuming(alt((
+        context(
+            "daily_parser",
+            value(Recurrence::Daily, tag_no_case("daily")),
+        ),
+        context(
+            "weekly_parser",
+            map(
+                preceded(
+                    tuple((tag_no_case("weekly"), space1, tag_no_case("on"), space1)),
+                    separated_list1(delimited(space0, char(','), space0), weekday_parser),
+                ),
+                |mut days| {
+                    days.sort_by_key(|d: &Weekday| d.number_days_from_monday());
+                    days.dedup();
+                    Recurrence::Weekly(days)
+                },
+            ),
+        ),
+        context(
+            "monthly_parser",
+            map(
+                preceded(
+                    tuple((tag_no_case("monthly"), space1, tag_no_case("on"), space1)),
+                    verify(nom::character::complete::u8, |d| (1..=31).contains(d)),
+                ),
+                Recurrence::Monthly,
+            ),
+        ),
+        context(
+            "after_done_parser",
+            map(
+                delimited(
+                    tuple((tag_no_case("every"), space1)),
+                    verify(nom::character::complete::u16, |n| *n > 0),
+                    tuple((
+                        space0,
+                        alt((tag_no_case("days"), tag_no_case("day"), tag_no_case("d"))),
+                        space1,
+                        tag_no_case("after"),
+                        space1,
+                        tag_no_case("done"),
+                    )),
+                ),
+                Recurrence::AfterDone,
+            ),
+        ),
+    )))
+    .parse(content)
+}
+
+fn weekday_parser<'a, E>(content: &'a str) -> Result<(&'a str, Weekday), nom::Err<E>>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+{
+    // the rest of the name is optional, `fri` and `friday` are the same day
+    let day = |long: &'static str, short: &'static str, day: Weekday| {
+        value(day, terminated(tag_no_case(short), opt(tag_no_case(long))))
+    };
+    context(
+        "weekday_parser",
+        alt((
+            day("day", "mon", Weekday::Monday),
+            day("sday", "tue", Weekday::Tuesday),
+            day("nesday", "wed", Weekday::Wednesday),
+            day("rsday", "thu", Weekday::Thursday),
+            day("day", "fri", Weekday::Friday),
+            day("urday", "sat", Weekday::Saturday),
+            day("day", "sun", Weekday::Sunday),
+        )),
+    )
+    .parse(content)
+}
+
+#[cfg(test)]
+mod testing {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use rstest::*;
+
+    fn date(year: i32, month: u8, day: u8) -> Date {
+        Date::from_calendar_date(year, Month::try_from(month).unwrap(), day).unwrap()
+    }
+
+    #[rstest]
+    #[case::daily("daily", Recurrence::Daily)]
+    #[case::weekly("weekly on fri", Recurrence::Weekly(vec![Weekday::Friday]))]
+    #[case::weekly_many(
+        "Weekly on Friday,mon , wednesday",
+        Recurrence::Weekly(vec![Weekday::Monday, Weekday::Wednesday, Weekday::Friday])
+    )]
+    #[case::monthly("monthly on 1", Recurrence::Monthly(1))]
+    #[case::after_done("every 10 days after done", Recurrence::AfterDone(10))]
+    #[case::after_done_short("every 3d after done", Recurrence::AfterDone(3))]
+    fn parse_happy_path(#[case] input: &str, #[case] expected: Recurrence) {
+        assert_eq!(input.parse::<Recurrence>().unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case::unknown("yearly")]
+    #[case::no_days("weekly on")]
+    #[case::bad_day("weekly on fryday")]
+    #[case::day_out_of_month("monthly on 32")]
+    #[case::never("every 0 days after done")]
+    fn parse_unhappy_path(#[case] input: &str) {
+        assert!(input.parse::<Recurrence>().is_err());
+    }
+
+    #[rstest]
+    #[case::written_back(Recurrence::Weekly(vec![Weekday::Monday, Weekday::Friday]))]
+    #[case::monthly(Recurrence::Monthly(15))]
+    #[case::after_done(Recurrence::AfterDone(7))]
+    fn reads_back(#[case] recurrence: Recurrence) {
+        assert_eq!(
+            recurrence.to_string().parse::<Recurrence>().unwrap(),
+            recurrence
+        );
+    }
+
+    #[rstest]
+    #[case::daily(
+        Recurrence::Daily,
+        date(2023, 10, 10),
+        date(2023, 10, 10),
+        date(2023, 10, 11)
+    )]
+    // 2023-10-13 is a friday
+    #[case::weekly(Recurrence::Weekly(vec![Weekday::Friday]), date(2023, 10, 13), date(2023, 10, 12), date(2023, 10, 20))]
+    #[case::weekly_done_late(Recurrence::Weekly(vec![Weekday::Monday, Weekday::Friday]), date(2023, 10, 13), date(2023, 10, 14), date(2023, 10, 16))]
+    #[case::monthly(
+        Recurrence::Monthly(1),
+        date(2023, 11, 1),
+        date(2023, 10, 28),
+        date(2023, 12, 1)
+    )]
+    #[case::monthly_short_month(
+        Recurrence::Monthly(31),
+        date(2023, 1, 31),
+        date(2023, 1, 31),
+        date(2023, 2, 28)
+    )]
+    #[case::monthly_missed(
+        Recurrence::Monthly(1),
+        date(2023, 11, 1),
+        date(2024, 1, 5),
+        date(2024, 2, 1)
+    )]
+    #[case::after_done(
+        Recurrence::AfterDone(10),
+        date(2023, 10, 1),
+        date(2023, 10, 5),
+        date(2023, 10, 15)
+    )]
+    fn next_instance(
+        #[case] recurrence: Recurrence,
+        #[case] due: Date,
+        #[case] done: Date,
+        #[case] expected: Date,
+    ) {
+        assert_eq!(recurrence.next(due, done), expected);
+    }
+}
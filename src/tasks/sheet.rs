@@ -0,0 +1,186 @@
+use std::str::FromStr;
+
+use time::Date;
+
+use crate::journal::frontmatter;
+
+use super::{bulk::Action, task::Area, task::Priority, task::Task, Error};
+
+/// The fields of a task written out to be edited by hand, with the description as the body.
+///
+/// ```text
+/// ---
+/// title: pay the rent
+/// area: home
+/// projects: flat
+/// people: alice, bob
+/// start: 2023-10-01
+/// deadline: 2023-10-05
+/// repeat: monthly on 1
+/// priority: high
+/// ---
+/// the description
+/// ```
+#[must_use]
+pub fn render(task: &Task) -> String {
+    format!(
+        "---\ntitle: {}\narea: {}\nprojects: {}\npeople: {}\nstart: {}\ndeadline: {}\nrepeat: {}\npriority: {}\n---\n{}\n",
+        task.title,
+        task.area
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+        task.projects.join(", "),
+        task.people.join(", "),
+        task.start.map(date).unwrap_or_default(),
+        task.end.map(date).unwrap_or_default(),
+        task.recurrence
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+        task.priority.map(|p| p.to_string()).unwrap_or_default(),
+        task.description.as_deref().unwrap_or_default(),
+    )
+}
+
+/// The edits that turn the task into what the sheet says, fields left out of the sheet are left
+/// alone. Dates are in the `timeutils` syntax, and an empty value clears the field.
+pub fn actions(input: &str, task: &Task) -> Result<Vec<Action>, Error> {
+    let (fields, body) = frontmatter::fields(input).map_err(Error::TaskSheetCouldNotBeParsed)?;
+
+    let mut actions = vec![];
+    for (key, value) in fields {
+        let value = value.trim();
+        match key.trim().to_lowercase().as_str() {
+            "title" => actions.push(Action::Title(value.to_owned())),
+            "area" => actions.push(Action::Area(match value {
+                "" => None,
+                v => Some(Area::from_str(v).map_err(|_e| Error::InvalidArea(v.to_owned()))?),
+            })),
+            "projects" | "project" => {
+                let projects = frontmatter::list_value(value);
+                actions.extend(list_edits(
+                    &task.projects,
+                    &projects,
+                    Action::AddProject,
+                    Action::RemoveProject,
+                ));
+            }
+            "people" | "person" => {
+                let people = frontmatter::list_value(value);
+                actions.extend(list_edits(
+                    &task.people,
+                    &people,
+                    Action::AddPerson,
+                    Action::RemovePerson,
+                ));
+            }
+            "start" => actions.push(Action::Start(non_empty(value))),
+            "deadline" | "due" => actions.push(Action::Deadline(non_empty(value))),
+            "repeat" => actions.push(Action::Repeat(
+                non_empty(value).map(|r| r.parse()).transpose()?,
+            )),
+            "priority" => actions.push(Action::Priority(match value {
+                "" => None,
+                v => {
+                    Some(Priority::from_str(v).map_err(|_e| Error::InvalidPriority(v.to_owned()))?)
+                }
+            })),
+            other => return Err(Error::UnknownTaskSheetField(other.to_owned())),
+        }
+    }
+    actions.push(Action::Description(non_empty(body.trim())));
+    Ok(actions)
+}
+
+fn list_edits(
+    current: &[String],
+    wanted: &[String],
+    add: fn(String) -> Action,
+    remove: fn(String) -> Action,
+) -> Vec<Action> {
+    current
+        .iter()
+        .filter(|c| !wanted.contains(c))
+        .cloned()
+        .map(remove)
+        .chain(
+            wanted
+                .iter()
+                .filter(|w| !current.contains(w))
+                .cloned()
+                .map(add),
+        )
+        .collect()
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    (!value.is_empty()).then(|| value.to_owned())
+}
+
+/// Written the way `timeutils` reads it back.
+fn date(d: Date) -> String {
+    format!("{}-{:02}-{:02}", d.year(), u8::from(d.month()), d.day())
+}
+
+#[cfg(test)]
+mod testing {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use rstest::*;
+
+    fn task() -> Task {
+        let now = time::OffsetDateTime::UNIX_EPOCH;
+        Task {
+            description: Some("before the fifth".to_owned()),
+            area: Some(Area::Home),
+            people: vec!["alice".to_owned()],
+            projects: vec!["flat".to_owned()],
+            start: Date::from_calendar_date(2023, time::Month::October, 1).ok(),
+            ..Task::new("pay the rent".to_owned(), now)
+        }
+    }
+
+    #[rstest]
+    fn unchanged_sheet_changes_nothing() {
+        let task = task();
+        let sheet = render(&task);
+        assert_eq!(
+            sheet,
+            "---\ntitle: pay the rent\narea: home\nprojects: flat\npeople: alice\nstart: 2023-10-01\ndeadline: \nrepeat: \npriority: \n---\nbefore the fifth\n"
+        );
+        let mut edited = task.clone();
+        for action in actions(&sheet, &task).unwrap() {
+            action
+                .apply(&mut edited, time::OffsetDateTime::UNIX_EPOCH)
+                .unwrap();
+        }
+        assert_eq!(edited, task);
+    }
+
+    #[rstest]
+    fn edited_sheet() {
+        let task = task();
+        let sheet =
+            "---\ntitle: pay the rent late\narea:\npeople: bob, alice\ndeadline: 2023-10-06\n---\n";
+        assert_eq!(
+            actions(sheet, &task).unwrap(),
+            vec![
+                Action::Title("pay the rent late".to_owned()),
+                Action::Area(None),
+                Action::AddPerson("bob".to_owned()),
+                Action::Deadline(Some("2023-10-06".to_owned())),
+                Action::Description(None),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::unknown_field("---\nweather: sunny\n---\n")]
+    #[case::unknown_area("---\narea: garden\n---\n")]
+    #[case::unknown_priority("---\npriority: urgent\n---\n")]
+    fn unhappy_sheets(#[case] sheet: &str) {
+        assert!(actions(sheet, &task()).is_err());
+    }
+}
@@ -1,17 +1,22 @@
 use std::{fmt::Display, ops::Sub, path::PathBuf, str::FromStr};
 
 use color_eyre::owo_colors::OwoColorize;
-use time::{formatting::Formattable, Date, OffsetDateTime};
+use time::{formatting::Formattable, Date, Duration, OffsetDateTime};
 
+use crate::context::Context;
 use crate::files::ToFileName;
+use crate::output::Row;
 
-use super::Error;
+use super::{
+    focus::Session, migration, recurrence::Recurrence, tracking, tracking::Interval, Error,
+};
 
 // const DATE_DISPLAY_FORMATTING: &str = "%Y-%m-%d %H:%M";
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Task {
+    pub schema_version: u32,
     pub id: i64,
     #[serde(with = "time::serde::rfc3339")]
     pub time_created: OffsetDateTime,
@@ -23,9 +28,50 @@ pub struct Task {
     pub projects: Vec<String>,
     pub start: Option<Date>,
     pub end: Option<Date>,
+    /// Where the task was created, `None` when nothing was captured
+    #[serde(default)]
+    pub context: Option<Context>,
+    /// Ids of the tasks that have to be done before this one
+    pub depends_on: Vec<i64>,
+    /// The id of the task this one is a part of
+    pub parent: Option<i64>,
+    /// When the task comes back once it is done
+    pub recurrence: Option<Recurrence>,
+    /// How much the task matters, `None` when it was never given
+    pub priority: Option<Priority>,
+    /// When the task was worked on, the last interval goes on while the task is active
+    pub time_log: Vec<Interval>,
+    /// The sessions of focused work on the task, interrupted ones included
+    pub focus_log: Vec<Session>,
 }
 
 impl Task {
+    /// A task to do with nothing else to it yet, its id comes from when it was created. The rest
+    /// is filled in with the struct update syntax, so a new field is only defaulted here.
+    #[must_use]
+    pub fn new(title: String, time_created: OffsetDateTime) -> Self {
+        Self {
+            schema_version: migration::CURRENT_VERSION,
+            id: time_created.unix_timestamp(),
+            time_created,
+            state_log: vec![State::ToDo(time_created)],
+            title,
+            description: None,
+            area: None,
+            people: vec![],
+            projects: vec![],
+            start: None,
+            end: None,
+            context: None,
+            depends_on: vec![],
+            parent: None,
+            recurrence: None,
+            priority: None,
+            time_log: vec![],
+            focus_log: vec![],
+        }
+    }
+
     pub fn print_colorful_with_current_duration(
         &self,
         current_time: OffsetDateTime,
@@ -57,6 +103,9 @@ impl Task {
 
         all_buf.push_str(&{
             let mut buf = String::new();
+            if let Some(priority) = &self.priority {
+                buf.push_str(&format!("{}{} ", "!".red().bold(), priority.red()));
+            };
             if let Some(content) = &self.area {
                 buf.push_str(&format!("{}{} ", "#".green(), content.green()));
             };
@@ -98,12 +147,165 @@ impl Task {
             buf
         });
 
+        if let Some(recurrence) = &self.recurrence {
+            all_buf.push('\n');
+            all_buf.push_str(&format!(
+                "{} {}",
+                "↻".bright_black(),
+                recurrence.bright_black()
+            ));
+        }
+
+        if !self.time_log.is_empty() {
+            let tracked = self.tracked(self.time_created, current_time, current_time);
+            all_buf.push('\n');
+            all_buf.push_str(&format!(
+                "{} {}",
+                "⏱".bright_black(),
+                format!("{} tracked", tracking::human(tracked)).bright_black()
+            ));
+            if self.active_since().is_some() {
+                all_buf.push_str(&format!(" {}", "▶ active".green().bold()));
+            }
+        }
+
+        if !self.focus_log.is_empty() {
+            let interrupted = self.focus_log.iter().filter(|s| s.interrupted).count();
+            let focused: Duration = self.focus_log.iter().map(|s| s.end - s.start).sum();
+            all_buf.push('\n');
+            all_buf.push_str(&format!(
+                "{} {}",
+                "🍅".bright_black(),
+                format!(
+                    "{} sessions, {} focused, {interrupted} interrupted",
+                    self.focus_log.len(),
+                    tracking::human(focused)
+                )
+                .bright_black()
+            ));
+        }
+
+        if let Some(context) = &self.context {
+            all_buf.push('\n');
+            all_buf.push_str(&format!("{}", context.bright_black()));
+        }
+
         Ok(all_buf)
     }
     #[must_use]
     pub fn current_state(&self) -> Option<&State> {
         self.state_log.last()
     }
+
+    /// The task that comes once this one is done, with its dates moved by its recurrence rule. A
+    /// task without dates starts on the day the rule gives.
+    #[must_use]
+    pub fn next_instance(&self, done_at: OffsetDateTime) -> Option<Self> {
+        let recurrence = self.recurrence.as_ref()?;
+        let done = done_at.date();
+        let due = self.end.or(self.start).unwrap_or(done);
+        let next = recurrence.next(due, done);
+        let shift = next - due;
+        Some(Self {
+            id: done_at.unix_timestamp(),
+            time_created: done_at,
+            state_log: vec![State::ToDo(done_at)],
+            start: match (self.start, self.end) {
+                (None, None) => Some(next),
+                (start, _) => start.map(|s| s.saturating_add(shift)),
+            },
+            end: self.end.map(|e| e.saturating_add(shift)),
+            time_log: vec![],
+            focus_log: vec![],
+            ..self.clone()
+        })
+    }
+
+    /// When the task started to be worked on, `None` unless it is active.
+    #[must_use]
+    pub fn active_since(&self) -> Option<OffsetDateTime> {
+        self.time_log
+            .last()
+            .filter(|i| i.end.is_none())
+            .map(|i| i.start)
+    }
+
+    /// How long the task was worked on between `from` and `to`.
+    #[must_use]
+    pub fn tracked(
+        &self,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+        now: OffsetDateTime,
+    ) -> Duration {
+        self.time_log.iter().map(|i| i.overlap(from, to, now)).sum()
+    }
+
+    /// Checks what can be got wrong by editing a task by hand.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.title.trim().is_empty() {
+            return Err(Error::TaskTitleIsEmpty);
+        }
+        if let (Some(start), Some(end)) = (self.start, self.end) {
+            if start > end {
+                return Err(Error::StartIsAfterDeadline(start, end));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Row for Task {
+    fn columns() -> Vec<&'static str> {
+        vec![
+            "id",
+            "state",
+            "title",
+            "area",
+            "projects",
+            "people",
+            "start",
+            "end",
+            "description",
+            "depends_on",
+            "parent",
+            "recurrence",
+            "priority",
+        ]
+    }
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.current_state()
+                .map(State::name)
+                .unwrap_or_default()
+                .to_owned(),
+            self.title.clone(),
+            self.area
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            self.projects.join(" "),
+            self.people.join(" "),
+            self.start.map(|d| d.to_string()).unwrap_or_default(),
+            self.end.map(|d| d.to_string()).unwrap_or_default(),
+            self.description.clone().unwrap_or_default(),
+            self.depends_on
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" "),
+            self.parent.map(|p| p.to_string()).unwrap_or_default(),
+            self.recurrence
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            self.priority
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+        ]
+    }
 }
 
 impl TryFrom<&PathBuf> for Task {
@@ -112,9 +314,9 @@ impl TryFrom<&PathBuf> for Task {
     fn try_from(value: &PathBuf) -> Result<Self, Self::Error> {
         let content = fs_extra::file::read_to_string(value).map_err(Error::FileCouldNotBeRead)?;
 
-        let task: Self = serde_json::from_str(&content)
-            .map_err(|e| Error::FileCouldNotDeserializeEntryFromJson(e, content))?;
-        Ok(task)
+        migration::REGISTRY
+            .read(&content)
+            .map_err(|e| Error::CouldNotMigrateTask(e, content))
     }
 }
 
@@ -122,11 +324,7 @@ impl TryFrom<PathBuf> for Task {
     type Error = Error;
 
     fn try_from(value: PathBuf) -> Result<Self, Self::Error> {
-        let content = fs_extra::file::read_to_string(value).map_err(Error::FileCouldNotBeRead)?;
-
-        let task: Self = serde_json::from_str(&content)
-            .map_err(|e| Error::FileCouldNotDeserializeEntryFromJson(e, content))?;
-        Ok(task)
+        Self::try_from(&value)
     }
 }
 impl ToFileName for Task {
@@ -151,6 +349,27 @@ pub enum State {
     ToDo(#[serde(with = "time::serde::rfc3339")] OffsetDateTime),
 }
 
+impl State {
+    /// When the task moved to the state.
+    #[must_use]
+    pub const fn at(&self) -> OffsetDateTime {
+        match self {
+            Self::Backlog(at) | Self::Abandoned(at, _) | Self::Done(at) | Self::ToDo(at) => *at,
+        }
+    }
+
+    /// The state in one plain word.
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Backlog(_) => "backlog",
+            Self::Abandoned(_, _) => "abandoned",
+            Self::Done(_) => "done",
+            Self::ToDo(_) => "todo",
+        }
+    }
+}
+
 impl Display for State {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -208,9 +427,56 @@ pub enum AreaParsingError {
     #[error("area given is not matching any particular ones")]
     AreaIsNotAMatch,
 }
+
+/// Ordered from the least to the most important.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Low => "low",
+                Self::Medium => "medium",
+                Self::High => "high",
+            }
+        )
+    }
+}
+
+impl FromStr for Priority {
+    type Err = PriorityParsingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "low" | "l" | "lo" => Ok(Self::Low),
+            "medium" | "m" | "me" | "med" | "medi" | "mediu" => Ok(Self::Medium),
+            "high" | "h" | "hi" | "hig" => Ok(Self::High),
+            _ => Err(PriorityParsingError::PriorityIsNotAMatch),
+        }
+    }
+}
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, thiserror::Error)]
+pub enum PriorityParsingError {
+    #[error("priority given is not one of low, medium or high")]
+    PriorityIsNotAMatch,
+}
 #[cfg(test)]
 mod testing {
+    #![allow(clippy::unwrap_used)]
+
     use super::*;
+    use crate::tasks::recurrence::Recurrence;
+    use rstest::*;
+    use time::{Month, Weekday};
 
     const fn is_normal<T: Sized + Send + Sync + Unpin>() {}
 
@@ -218,4 +484,50 @@ mod testing {
     const fn normal_types() {
         is_normal::<Task>();
     }
+
+    fn date(year: i32, month: u8, day: u8) -> Date {
+        Date::from_calendar_date(year, Month::try_from(month).unwrap(), day).unwrap()
+    }
+
+    fn task(recurrence: Option<Recurrence>, start: Option<Date>, end: Option<Date>) -> Task {
+        let now = OffsetDateTime::UNIX_EPOCH;
+        Task {
+            state_log: vec![State::ToDo(now), State::Done(now)],
+            area: Some(Area::Work),
+            start,
+            end,
+            recurrence,
+            ..Task::new("weekly review".to_owned(), now)
+        }
+    }
+
+    #[rstest]
+    // 2023-10-13 is a friday
+    #[case::shifted(
+        Some(date(2023, 10, 12)),
+        Some(date(2023, 10, 13)),
+        Some(date(2023, 10, 19)),
+        Some(date(2023, 10, 20))
+    )]
+    #[case::no_dates(None, None, Some(date(2023, 10, 20)), None)]
+    fn next_instance(
+        #[case] start: Option<Date>,
+        #[case] end: Option<Date>,
+        #[case] next_start: Option<Date>,
+        #[case] next_end: Option<Date>,
+    ) {
+        let done_at = date(2023, 10, 13).midnight().assume_utc();
+        let weekly = Some(Recurrence::Weekly(vec![Weekday::Friday]));
+        let next = task(weekly, start, end).next_instance(done_at).unwrap();
+        assert_eq!(next.id, done_at.unix_timestamp());
+        assert_eq!(next.state_log, vec![State::ToDo(done_at)]);
+        assert_eq!((next.start, next.end), (next_start, next_end));
+        assert_eq!(next.area, Some(Area::Work));
+    }
+
+    #[rstest]
+    fn no_next_instance_without_recurrence() {
+        let done_at = OffsetDateTime::UNIX_EPOCH;
+        assert_eq!(task(None, None, None).next_instance(done_at), None);
+    }
 }
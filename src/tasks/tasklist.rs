@@ -1,8 +1,12 @@
 use std::path::PathBuf;
 
-use crate::{files::is_json, fold_or_err};
+use crate::{files::list_json, index};
 
-use super::{task::Task, Error};
+use super::{
+    migration,
+    task::{State, Task},
+    Error,
+};
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -44,25 +48,150 @@ impl TryFrom<PathBuf> for TaskDescription {
     }
 }
 
+impl TaskList {
+    /// The one task whose id contains the identifier.
+    pub fn find(&self, task_identifier: i64) -> Result<&TaskDescription, Error> {
+        let identifier = task_identifier.to_string();
+        let mut found = self
+            .0
+            .iter()
+            .filter(|x| x.task.id.to_string().contains(&identifier));
+        let first = found.next().ok_or(Error::NoTasksFound)?;
+        if found.next().is_some() {
+            return Err(Error::MoreThanOneTaskWasFound(Box::new(
+                self.0
+                    .iter()
+                    .filter(|x| x.task.id.to_string().contains(&identifier))
+                    .cloned()
+                    .collect(),
+            )));
+        }
+        Ok(first)
+    }
+
+    /// The task with exactly that id.
+    #[must_use]
+    pub fn get(&self, id: i64) -> Option<&TaskDescription> {
+        self.0.iter().find(|t| t.task.id == id)
+    }
+
+    /// The dependencies of the task that are not done yet, the ones that are not in the list are
+    /// left out.
+    #[must_use]
+    pub fn open_dependencies(&self, task: &Task) -> Vec<i64> {
+        task.depends_on
+            .iter()
+            .copied()
+            .filter(|id| {
+                self.get(*id)
+                    .is_some_and(|d| !matches!(d.task.current_state(), Some(State::Done(_))))
+            })
+            .collect()
+    }
+
+    /// The task that is being worked on, there is at most one.
+    #[must_use]
+    pub fn active(&self) -> Option<&TaskDescription> {
+        self.0.iter().find(|t| t.task.active_since().is_some())
+    }
+
+    #[must_use]
+    pub fn is_blocked(&self, task: &Task) -> bool {
+        !self.open_dependencies(task).is_empty()
+    }
+
+    /// The tasks that are parts of the task.
+    #[must_use]
+    pub fn children(&self, id: i64) -> Vec<&TaskDescription> {
+        self.0
+            .iter()
+            .filter(|t| t.task.parent == Some(id))
+            .collect()
+    }
+
+    /// How many of the parts of the task are done, out of how many there are, `None` when the task
+    /// has no parts. Abandoned parts are not counted.
+    #[must_use]
+    pub fn progress(&self, id: i64) -> Option<(usize, usize)> {
+        let children: Vec<&Task> = self
+            .children(id)
+            .into_iter()
+            .map(|c| &c.task)
+            .filter(|c| !matches!(c.current_state(), Some(State::Abandoned(_, _))))
+            .collect();
+        (!children.is_empty()).then(|| {
+            (
+                children
+                    .iter()
+                    .filter(|c| matches!(c.current_state(), Some(State::Done(_))))
+                    .count(),
+                children.len(),
+            )
+        })
+    }
+
+    /// The parts of the task, and their parts, that are neither done nor abandoned.
+    #[must_use]
+    pub fn open_descendants(&self, id: i64) -> Vec<&TaskDescription> {
+        let mut open = vec![];
+        let mut to_visit = vec![id];
+        while let Some(id) = to_visit.pop() {
+            for child in self.children(id) {
+                // a hand edited task can still be its own ancestor
+                if child.task.id == id || open.contains(&child) {
+                    continue;
+                }
+                to_visit.push(child.task.id);
+                if matches!(
+                    child.task.current_state(),
+                    Some(State::ToDo(_) | State::Backlog(_))
+                ) {
+                    open.push(child);
+                }
+            }
+        }
+        open
+    }
+
+    /// Whether the task would end up depending on itself if it depended on the other one.
+    #[must_use]
+    pub fn would_cycle(&self, task: i64, dependency: i64) -> bool {
+        let mut to_visit = vec![dependency];
+        let mut visited = vec![];
+        while let Some(id) = to_visit.pop() {
+            if id == task {
+                return true;
+            }
+            if visited.contains(&id) {
+                continue;
+            }
+            visited.push(id);
+            if let Some(d) = self.get(id) {
+                to_visit.extend(&d.task.depends_on);
+            }
+        }
+        false
+    }
+}
+
 impl TryFrom<&PathBuf> for TaskList {
     type Error = Error;
 
     fn try_from(value: &PathBuf) -> Result<Self, Self::Error> {
-        let mut task_list = fs_extra::dir::get_dir_content(value)
-            .map_err(Error::DirCouldNotBeRead)?
-            .files
-            .into_iter()
-            .map(PathBuf::from)
-            .filter(is_json)
-            .map(TaskDescription::try_from)
-            .try_fold(vec![], fold_or_err)?;
+        let files = list_json(value).map_err(Error::DirCouldNotBeRead)?;
+        let mut task_list = index::load(value, files, migration::CURRENT_VERSION, |p| {
+            TaskDescription::try_from(p.to_path_buf())
+        })?;
         task_list.sort();
         Ok(Self(task_list))
     }
 }
 #[cfg(test)]
 mod testing {
+    #![allow(clippy::indexing_slicing)]
+
     use super::*;
+    use rstest::*;
 
     const fn is_normal<T: Sized + Send + Sync + Unpin>() {}
 
@@ -70,4 +199,81 @@ mod testing {
     const fn normal_types() {
         is_normal::<TaskList>();
     }
+
+    fn task_list(tasks: &[(i64, &[i64], bool)]) -> TaskList {
+        task_tree(
+            &tasks
+                .iter()
+                .map(|(id, d, done)| (*id, *d, *done, None))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    fn task_tree(tasks: &[(i64, &[i64], bool, Option<i64>)]) -> TaskList {
+        let now = time::OffsetDateTime::UNIX_EPOCH;
+        TaskList(
+            tasks
+                .iter()
+                .map(|(id, depends_on, done, parent)| TaskDescription {
+                    task: Task {
+                        id: *id,
+                        state_log: if *done {
+                            vec![State::ToDo(now), State::Done(now)]
+                        } else {
+                            vec![State::ToDo(now)]
+                        },
+                        depends_on: depends_on.to_vec(),
+                        parent: *parent,
+                        ..Task::new(format!("task {id}"), now)
+                    },
+                    file_name: format!("{id}.json"),
+                })
+                .collect(),
+        )
+    }
+
+    #[rstest]
+    fn blocked_until_done() {
+        let tasks = task_list(&[(1, &[2, 3, 9], false), (2, &[], true), (3, &[], false)]);
+        let first = &tasks.0[0].task;
+        assert_eq!(tasks.open_dependencies(first), vec![3]);
+        assert!(tasks.is_blocked(first));
+        assert!(!tasks.is_blocked(&tasks.0[1].task));
+    }
+
+    #[rstest]
+    #[case::itself(1, 1, true)]
+    #[case::direct(2, 1, true)]
+    #[case::transitive(3, 1, true)]
+    #[case::shortcut(1, 3, false)]
+    #[case::unrelated(4, 3, false)]
+    fn cycles(#[case] task: i64, #[case] dependency: i64, #[case] expected: bool) {
+        // 1 -> 2 -> 3
+        let tasks = task_list(&[
+            (1, &[2], false),
+            (2, &[3], false),
+            (3, &[], false),
+            (4, &[], false),
+        ]);
+        assert_eq!(tasks.would_cycle(task, dependency), expected);
+    }
+
+    #[rstest]
+    fn parts_of_a_task() {
+        // 1 has 2 and 3, 3 has 4
+        let tasks = task_tree(&[
+            (1, &[], false, None),
+            (2, &[], true, Some(1)),
+            (3, &[], false, Some(1)),
+            (4, &[], false, Some(3)),
+        ]);
+        assert_eq!(tasks.progress(1), Some((1, 2)));
+        assert_eq!(tasks.progress(2), None);
+        let open: Vec<i64> = tasks
+            .open_descendants(1)
+            .iter()
+            .map(|t| t.task.id)
+            .collect();
+        assert_eq!(open, vec![3, 4]);
+    }
 }
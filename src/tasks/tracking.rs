@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+
+use time::{Duration, OffsetDateTime};
+
+use crate::output::Row;
+
+use super::tasklist::TaskList;
+
+/// A stretch of time spent working on a task, it goes on while it has no end.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Interval {
+    #[serde(with = "time::serde::rfc3339")]
+    pub start: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub end: Option<OffsetDateTime>,
+}
+
+impl Interval {
+    /// How much of the interval falls between `from` and `to`, one that goes on ends at `now`.
+    #[must_use]
+    pub fn overlap(
+        &self,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+        now: OffsetDateTime,
+    ) -> Duration {
+        let start = self.start.max(from);
+        let end = self.end.unwrap_or(now).min(to);
+        (end - start).max(Duration::ZERO)
+    }
+}
+
+/// The time tracked on a task, a project or an area.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Tracked {
+    /// One of task, project or area
+    pub by: &'static str,
+    pub name: String,
+    pub seconds: i64,
+}
+
+impl Row for Tracked {
+    fn columns() -> Vec<&'static str> {
+        vec!["by", "name", "time", "seconds"]
+    }
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.by.to_owned(),
+            self.name.clone(),
+            human(Duration::seconds(self.seconds)),
+            self.seconds.to_string(),
+        ]
+    }
+}
+
+/// The time tracked between `from` and `to`, per task, then per project and then per area, the
+/// most worked on first in each of them. A task counts towards every one of its projects, the
+/// ones without a project or an area are put under `none`.
+#[must_use]
+pub fn report(
+    tasks: &TaskList,
+    from: OffsetDateTime,
+    to: OffsetDateTime,
+    now: OffsetDateTime,
+) -> Vec<Tracked> {
+    let mut per_task = vec![];
+    let mut per_project: BTreeMap<String, i64> = BTreeMap::new();
+    let mut per_area: BTreeMap<String, i64> = BTreeMap::new();
+    for task in tasks.0.iter().map(|t| &t.task) {
+        let seconds = task.tracked(from, to, now).whole_seconds();
+        if seconds == 0 {
+            continue;
+        }
+        per_task.push((format!("{} {}", task.id, task.title), seconds));
+        if task.projects.is_empty() {
+            *per_project.entry("none".to_owned()).or_default() += seconds;
+        }
+        for project in &task.projects {
+            *per_project.entry(project.clone()).or_default() += seconds;
+        }
+        *per_area
+            .entry(
+                task.area
+                    .as_ref()
+                    .map_or_else(|| "none".to_owned(), ToString::to_string),
+            )
+            .or_default() += seconds;
+    }
+
+    [
+        ("task", per_task),
+        ("project", per_project.into_iter().collect()),
+        ("area", per_area.into_iter().collect()),
+    ]
+    .into_iter()
+    .flat_map(|(by, mut totals)| {
+        totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        totals
+            .into_iter()
+            .map(move |(name, seconds)| Tracked { by, name, seconds })
+    })
+    .collect()
+}
+
+/// Hours and minutes, like `1h 05m`.
+#[must_use]
+pub fn human(duration: Duration) -> String {
+    format!(
+        "{}h {:02}m",
+        duration.whole_hours(),
+        duration.whole_minutes() % 60_i64
+    )
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::tasks::{
+        task::{Area, Task},
+        tasklist::TaskDescription,
+    };
+    use rstest::*;
+
+    const fn is_normal<T: Sized + Send + Sync + Unpin>() {}
+
+    #[test]
+    const fn normal_types() {
+        is_normal::<Interval>();
+        is_normal::<Tracked>();
+    }
+
+    fn at(hours: i64) -> OffsetDateTime {
+        OffsetDateTime::UNIX_EPOCH + Duration::hours(hours)
+    }
+
+    fn task(
+        id: i64,
+        projects: &[&str],
+        area: Option<Area>,
+        time_log: Vec<Interval>,
+    ) -> TaskDescription {
+        TaskDescription {
+            task: Task {
+                id,
+                area,
+                projects: projects.iter().map(|&p| p.to_owned()).collect(),
+                time_log,
+                ..Task::new(format!("task {id}"), at(0))
+            },
+            file_name: format!("{id}.json"),
+        }
+    }
+
+    #[rstest]
+    #[case::inside(at(2), Some(at(3)), 1)]
+    #[case::clipped(at(0), Some(at(3)), 2)]
+    #[case::outside(at(20), Some(at(30)), 0)]
+    #[case::goes_on(at(5), None, 2)]
+    fn overlaps(
+        #[case] start: OffsetDateTime,
+        #[case] end: Option<OffsetDateTime>,
+        #[case] hours: i64,
+    ) {
+        let interval = Interval { start, end };
+        assert_eq!(
+            interval.overlap(at(1), at(10), at(7)),
+            Duration::hours(hours)
+        );
+    }
+
+    #[rstest]
+    fn tracked_per_task_project_and_area() {
+        let tasks = TaskList(vec![
+            task(
+                1,
+                &["prmait"],
+                Some(Area::Work),
+                vec![Interval {
+                    start: at(1),
+                    end: Some(at(2)),
+                }],
+            ),
+            task(
+                2,
+                &["prmait", "docs"],
+                None,
+                vec![
+                    Interval {
+                        start: at(2),
+                        end: Some(at(4)),
+                    },
+                    Interval {
+                        start: at(5),
+                        end: None,
+                    },
+                ],
+            ),
+            task(3, &[], None, vec![]),
+        ]);
+        let report: Vec<(&str, String, i64)> = report(&tasks, at(0), at(24), at(6))
+            .into_iter()
+            .map(|t| (t.by, t.name, t.seconds / 3600))
+            .collect();
+        assert_eq!(
+            report,
+            [
+                ("task", "2 task 2".to_owned(), 3),
+                ("task", "1 task 1".to_owned(), 1),
+                ("project", "prmait".to_owned(), 4),
+                ("project", "docs".to_owned(), 3),
+                ("area", "none".to_owned(), 3),
+                ("area", "work".to_owned(), 1),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn human_durations() {
+        assert_eq!(human(Duration::minutes(65)), "1h 05m");
+        assert_eq!(human(Duration::seconds(59)), "0h 00m");
+    }
+}
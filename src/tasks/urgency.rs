@@ -0,0 +1,204 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use time::OffsetDateTime;
+
+use super::{
+    task::{Priority, State, Task},
+    tasklist::TaskList,
+};
+
+/// What is added to the urgency of a task for how much it matters.
+const PRIORITY_HIGH: f64 = 6.0_f64;
+const PRIORITY_MEDIUM: f64 = 3.9_f64;
+const PRIORITY_LOW: f64 = 1.8_f64;
+/// The most a deadline adds, once it is a week overdue.
+const DEADLINE: f64 = 12.0_f64;
+/// The most the age of a task adds, once it is a year old.
+const AGE: f64 = 2.0_f64;
+/// What is taken off while the task waits on others.
+const BLOCKED: f64 = -5.0_f64;
+
+/// How urgent the tasks of each project are on top of everything else.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Config {
+    /// Added to the urgency of every task of the project, negative weights push them down
+    #[serde(default)]
+    pub projects: BTreeMap<String, i32>,
+}
+
+/// The order tasks are listed in.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, clap::ValueEnum)]
+pub enum Sort {
+    /// Oldest first
+    #[default]
+    Created,
+    /// Most urgent first
+    Urgency,
+}
+
+/// How soon the task should be done, higher is sooner. It adds up the priority, how close the
+/// deadline is, how old the task is and the weights of its projects, and takes some off while the
+/// task is blocked.
+#[must_use]
+pub fn score(task: &Task, blocked: bool, now: OffsetDateTime, config: &Config) -> f64 {
+    let priority = match task.priority {
+        Some(Priority::High) => PRIORITY_HIGH,
+        Some(Priority::Medium) => PRIORITY_MEDIUM,
+        Some(Priority::Low) => PRIORITY_LOW,
+        None => 0.0_f64,
+    };
+    let deadline = task.end.map_or(0.0_f64, |end| {
+        // from a fifth two weeks ahead up to all of it a week overdue
+        let days_left = (end - now.date()).whole_days().clamp(-7_i64, 14_i64);
+        DEADLINE * (0.2_f64 + f64::from(14_i32 - days(days_left)) * 0.8_f64 / 21.0_f64)
+    });
+    let age_in_days = (now - task.time_created).whole_days().clamp(0_i64, 365_i64);
+    let age = AGE * f64::from(days(age_in_days)) / 365.0_f64;
+    let blocked = if blocked { BLOCKED } else { 0.0_f64 };
+    let projects: i32 = task
+        .projects
+        .iter()
+        .filter_map(|p| config.projects.get(p))
+        .sum();
+    priority + deadline + age + blocked + f64::from(projects)
+}
+
+/// Days that were clamped to fit.
+fn days(days: i64) -> i32 {
+    i32::try_from(days).unwrap_or_default()
+}
+
+/// The same tasks, most urgent first. Tasks that are as urgent keep their order.
+#[must_use]
+pub fn sorted(tasks: TaskList, now: OffsetDateTime, config: &Config) -> TaskList {
+    // what blocks is looked up once, not for every task that depends on something
+    let open: HashSet<i64> = tasks
+        .0
+        .iter()
+        .filter(|t| !matches!(t.task.current_state(), Some(State::Done(_))))
+        .map(|t| t.task.id)
+        .collect();
+    let scores: HashMap<i64, f64> = tasks
+        .0
+        .iter()
+        .map(|t| {
+            let blocked = t.task.depends_on.iter().any(|id| open.contains(id));
+            (t.task.id, score(&t.task, blocked, now, config))
+        })
+        .collect();
+    let score_of = |id: i64| scores.get(&id).copied().unwrap_or_default();
+    let mut tasks = tasks.0;
+    tasks.sort_by(|a, b| score_of(b.task.id).total_cmp(&score_of(a.task.id)));
+    TaskList(tasks)
+}
+
+#[cfg(test)]
+mod testing {
+    use super::*;
+    use crate::tasks::tasklist::TaskDescription;
+    use rstest::*;
+    use time::{Date, Duration};
+
+    const fn is_normal<T: Sized + Send + Sync + Unpin>() {}
+
+    #[test]
+    const fn normal_types() {
+        is_normal::<Config>();
+        is_normal::<Sort>();
+    }
+
+    fn now() -> OffsetDateTime {
+        OffsetDateTime::UNIX_EPOCH + Duration::days(1000)
+    }
+
+    fn task(id: i64, priority: Option<Priority>, end: Option<Date>) -> TaskDescription {
+        TaskDescription {
+            task: Task {
+                id,
+                end,
+                priority,
+                ..Task::new(id.to_string(), now())
+            },
+            file_name: format!("{id}.json"),
+        }
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9_f64
+    }
+
+    #[rstest]
+    #[case::nothing(None, None, 0.0_f64)]
+    #[case::high(Some(Priority::High), None, PRIORITY_HIGH)]
+    #[case::far_deadline(None, Some(100_i64), DEADLINE * 0.2_f64)]
+    #[case::due_today(None, Some(0_i64), DEADLINE * (0.2_f64 + 14.0_f64 * 0.8_f64 / 21.0_f64))]
+    #[case::long_overdue(Some(Priority::Low), Some(-30_i64), PRIORITY_LOW + DEADLINE)]
+    fn scores(
+        #[case] priority: Option<Priority>,
+        #[case] due_in: Option<i64>,
+        #[case] expected: f64,
+    ) {
+        let end = due_in.map(|d| now().date() + Duration::days(d));
+        let task = task(1, priority, end);
+        let tasks = TaskList(vec![task.clone()]);
+        let score = score(
+            &task.task,
+            tasks.is_blocked(&task.task),
+            now(),
+            &Config::default(),
+        );
+        assert!(close(score, expected), "{score} is not {expected}");
+    }
+
+    #[rstest]
+    fn blocked_old_and_weighted() {
+        let mut old = task(1, None, None);
+        old.task.time_created = now() - Duration::days(730);
+        old.task.projects = vec!["prmait".to_owned()];
+        old.task.depends_on = vec![2];
+        let tasks = TaskList(vec![old.clone(), task(2, None, None)]);
+        let config = Config {
+            projects: BTreeMap::from([("prmait".to_owned(), 3_i32)]),
+        };
+        let score = score(&old.task, tasks.is_blocked(&old.task), now(), &config);
+        assert!(close(score, AGE + BLOCKED + 3.0_f64), "{score}");
+    }
+
+    #[rstest]
+    fn most_urgent_first() {
+        let tomorrow = now().date() + Duration::days(1);
+        let tasks = TaskList(vec![
+            task(1, None, None),
+            task(2, Some(Priority::Low), None),
+            task(3, None, Some(tomorrow)),
+            task(4, None, None),
+        ]);
+        let ids: Vec<i64> = sorted(tasks, now(), &Config::default())
+            .0
+            .iter()
+            .map(|t| t.task.id)
+            .collect();
+        assert_eq!(ids, [3, 2, 1, 4]);
+    }
+
+    #[rstest]
+    fn blocked_until_the_dependency_is_done() {
+        let mut blocked = task(1, Some(Priority::High), None);
+        blocked.task.depends_on = vec![2, 9];
+        let mut done = task(2, None, None);
+        done.task.state_log.push(State::Done(now()));
+        let order = |tasks: TaskList| -> Vec<i64> {
+            sorted(tasks, now(), &Config::default())
+                .0
+                .iter()
+                .map(|t| t.task.id)
+                .collect()
+        };
+        let waiting = TaskList(vec![blocked.clone(), task(2, Some(Priority::Low), None)]);
+        assert_eq!(order(waiting), [2, 1]);
+        assert_eq!(order(TaskList(vec![done, blocked])), [1, 2]);
+    }
+}